@@ -0,0 +1,343 @@
+use crate::config::Config;
+use speki_core::reviews::Recall;
+use Action::{Card as C, Review as R};
+
+#[derive(Clone, Copy, Debug)]
+pub enum CardAction {
+    NewDependency,
+    OldDependency,
+    NewDependent,
+    OldDependent,
+    Edit,
+    Delete,
+    /// Turn card into an instance of a new class
+    NewClass,
+    /// Turn card into an instance of an old class
+    OldClass,
+    NewAttribute,
+    OldAttribute,
+    FillAttribute,
+    SetBackRef,
+    /// Set the parent class of current class
+    ParentClass,
+
+    NewCard,
+    /// Turn card into statement
+    IntoStatement,
+    /// Turn a card into an attribute
+    IntoAttribute,
+    /// Turn card into a class
+    IntoClass,
+
+    IntoInstance,
+    IntoEvent,
+    /// Add or remove the back to front card
+    Reverse,
+    Suspend,
+    /// Unsuspend and unsnooze
+    Unsuspend,
+    /// Keep out of review for some days
+    Snooze,
+}
+
+/// Actions that only make sense while reviewing.
+#[derive(Clone, Copy, Debug)]
+pub enum ReviewAction {
+    Grade(Recall),
+    Skip,
+    Undo,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+    Review(ReviewAction),
+    Card(CardAction),
+    Find,
+    Help,
+    Exit,
+}
+
+pub struct ActionInfo {
+    /// Identifies the action in the keybindings of the config.
+    pub name: &'static str,
+    pub action: Action,
+    pub default_keys: &'static [&'static str],
+    pub description: &'static str,
+}
+
+const fn info(
+    name: &'static str,
+    action: Action,
+    default_keys: &'static [&'static str],
+    description: &'static str,
+) -> ActionInfo {
+    ActionInfo {
+        name,
+        action,
+        default_keys,
+        description,
+    }
+}
+
+/// Every action available in the card view, in the order they're listed in the help.
+pub const ACTIONS: &[ActionInfo] = &[
+    info(
+        "grade_1",
+        R(ReviewAction::Grade(Recall::None)),
+        &["1"],
+        "failed to recall backside, where the backside info seems new to you",
+    ),
+    info(
+        "grade_2",
+        R(ReviewAction::Grade(Recall::Late)),
+        &["2"],
+        "failed to recall backside but the information was familiar to you when reading it",
+    ),
+    info(
+        "grade_3",
+        R(ReviewAction::Grade(Recall::Some)),
+        &["3"],
+        "successfully recalled backside after some thinking",
+    ),
+    info(
+        "grade_4",
+        R(ReviewAction::Grade(Recall::Perfect)),
+        &["4"],
+        "successfully recalled backside without hesitation",
+    ),
+    info("skip", R(ReviewAction::Skip), &["skip", "s"], "skip card"),
+    info(
+        "undo",
+        R(ReviewAction::Undo),
+        &["undo", "u"],
+        "undo the grade of the previous card, edits and new cards are kept",
+    ),
+    info(
+        "old_dependency",
+        C(CardAction::OldDependency),
+        &["y"],
+        "add new dependency, from cards in your collections",
+    ),
+    info(
+        "new_dependency",
+        C(CardAction::NewDependency),
+        &["Y"],
+        "add new dependency by creating a new card",
+    ),
+    info(
+        "old_dependent",
+        C(CardAction::OldDependent),
+        &["t"],
+        "add new dependent, from cards in your collections",
+    ),
+    info(
+        "new_dependent",
+        C(CardAction::NewDependent),
+        &["T"],
+        "add new dependent, by creating a new card",
+    ),
+    info(
+        "old_class",
+        C(CardAction::OldClass),
+        &["c"],
+        "make the card an instance of an existing class",
+    ),
+    info(
+        "new_class",
+        C(CardAction::NewClass),
+        &["C"],
+        "make the card an instance of a new class",
+    ),
+    info(
+        "parent_class",
+        C(CardAction::ParentClass),
+        &["p"],
+        "set the parent class of a class card",
+    ),
+    info(
+        "old_attribute",
+        C(CardAction::OldAttribute),
+        &["a"],
+        "turn the card into an attribute of its instance dependency",
+    ),
+    info(
+        "new_attribute",
+        C(CardAction::NewAttribute),
+        &["A"],
+        "create a new attribute for the class of an instance card",
+    ),
+    info(
+        "fill_attribute",
+        C(CardAction::FillAttribute),
+        &["fa"],
+        "answer an attribute of an instance card",
+    ),
+    info(
+        "back_ref",
+        C(CardAction::SetBackRef),
+        &["ref"],
+        "use another card as the back side",
+    ),
+    info(
+        "new_card",
+        C(CardAction::NewCard),
+        &["n"],
+        "add a new card in the same category",
+    ),
+    info(
+        "into_class",
+        C(CardAction::IntoClass),
+        &["ic"],
+        "turn the card into a class",
+    ),
+    info(
+        "into_attribute",
+        C(CardAction::IntoAttribute),
+        &["ia"],
+        "turn the card into an attribute card",
+    ),
+    info(
+        "into_statement",
+        C(CardAction::IntoStatement),
+        &["is"],
+        "turn the card into a statement",
+    ),
+    info(
+        "into_instance",
+        C(CardAction::IntoInstance),
+        &["ii"],
+        "turn the card into an instance",
+    ),
+    info(
+        "into_event",
+        C(CardAction::IntoEvent),
+        &["ie"],
+        "turn the card into an event",
+    ),
+    info(
+        "reverse",
+        C(CardAction::Reverse),
+        &["rev"],
+        "add or remove a reverse card, asking from back to front",
+    ),
+    info(
+        "suspend",
+        C(CardAction::Suspend),
+        &["S"],
+        "suspend the card, so it's not reviewed anymore",
+    ),
+    info(
+        "unsuspend",
+        C(CardAction::Unsuspend),
+        &["U"],
+        "unsuspend the card, and bring it back if it's snoozed",
+    ),
+    info(
+        "snooze",
+        C(CardAction::Snooze),
+        &["z"],
+        "keep the card out of review for some days",
+    ),
+    info(
+        "edit",
+        C(CardAction::Edit),
+        &["edit"],
+        "open the card in vim (must be installed)",
+    ),
+    info(
+        "delete",
+        C(CardAction::Delete),
+        &["delete"],
+        "delete the card",
+    ),
+    info(
+        "find",
+        Action::Find,
+        &["find"],
+        "search for a card and view it",
+    ),
+    info("exit", Action::Exit, &["exit"], "back to main menu"),
+    info(
+        "help",
+        Action::Help,
+        &["help", "?"],
+        "open this help message",
+    ),
+];
+
+/// The keys of each action, with the keybindings of the config applied.
+pub struct Keymap(Vec<(&'static ActionInfo, Vec<String>)>);
+
+impl Keymap {
+    pub fn load() -> Self {
+        let config = Config::load();
+        let mut custom: Vec<(&'static ActionInfo, Vec<String>)> = vec![];
+        let mut defaults: Vec<(&'static ActionInfo, Vec<String>)> = vec![];
+
+        for info in ACTIONS {
+            match config.keybindings.get(info.name) {
+                Some(keys) => custom.push((info, keys.clone())),
+                None => defaults.push((
+                    info,
+                    info.default_keys
+                        .iter()
+                        .map(|key| key.to_string())
+                        .collect(),
+                )),
+            }
+        }
+
+        // Remapped keys win over default ones, so a key can be moved to another action
+        // without having to remap the action it belonged to.
+        let mut entries = custom;
+        entries.extend(defaults);
+        Self(entries)
+    }
+
+    pub fn parse(&self, input: &str) -> Option<Action> {
+        let input = input.trim();
+        self.0
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|key| key == input))
+            .map(|(info, _)| info.action)
+    }
+
+    pub fn help(&self, review_mode: bool) -> String {
+        let mut lines = vec![];
+
+        for info in ACTIONS {
+            if !review_mode && matches!(info.action, Action::Review(_)) {
+                continue;
+            }
+
+            let Some((_, keys)) = self.0.iter().find(|(entry, _)| entry.name == info.name) else {
+                continue;
+            };
+
+            let keys = if keys.is_empty() {
+                "(unbound)".to_string()
+            } else {
+                keys.join(" | ")
+            };
+
+            lines.push((keys, info.description));
+        }
+
+        let width = lines
+            .iter()
+            .map(|(keys, _)| keys.len())
+            .max()
+            .unwrap_or_default();
+        let mut out = String::from("\n\npossible commands:\n\n");
+        for (keys, description) in lines {
+            out.push_str(&format!(
+                "{:<width$} => {}\n",
+                keys,
+                description,
+                width = width
+            ));
+        }
+
+        out
+    }
+}
@@ -1,12 +1,18 @@
 use crate::{
+    cloze::{add_cloze, is_cloze},
     incread::{inc_path, textstuff},
-    utils::{choose_folder, clear_terminal, get_input_opt, notify},
+    reverse::{add_reverse, category_default, toggle_category_default},
+    utils::{category_from_path, choose_folder, clear_terminal, find_card, get_input_opt, notify},
 };
+use clap::{Args, ValueEnum};
 use console::style;
 use dialoguer::{theme::ColorfulTheme, Input, Select};
 use speki_core::{
+    attribute::Attribute,
+    card::{AnyType, AttributeCard, BackSide, ClassCard, EventCard, InstanceCard, StatementCard},
     categories::Category,
     common::{filename_sanitizer, CardId},
+    Card,
 };
 use std::{fs::read_to_string, io::Write, path::PathBuf, str::FromStr};
 
@@ -33,6 +39,16 @@ pub fn add_card(category: &Category) -> Option<CardId> {
         return None;
     }
 
+    if is_cloze(&front) {
+        return match add_cloze(&front, category) {
+            Ok(ids) => ids.first().copied(),
+            Err(msg) => {
+                notify(msg);
+                None
+            }
+        };
+    }
+
     let s = style("back").bold();
     let back: String = Input::new()
         .with_prompt(s.to_string())
@@ -55,11 +71,210 @@ pub fn add_card(category: &Category) -> Option<CardId> {
             _ => panic!(),
         }
     } else {
-        speki_core::add_card(front, back, category)
+        let id = speki_core::add_card(front, back, category);
+        if category_default(category) {
+            let _ = add_reverse(id);
+        }
+        id
     }
     .into()
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum NewCardType {
+    #[default]
+    Normal,
+    Class,
+    Instance,
+    Statement,
+    Event,
+    Attribute,
+    /// One card per deletion, e.g. "The {{c1::mitochondria}} is the powerhouse of the cell"
+    Cloze,
+}
+
+#[derive(Args, Debug)]
+pub struct AddArgs {
+    /// Front side of the card, or the name for classes and instances
+    front: String,
+    /// Back side of the card. A normal card without a back side is added as unfinished
+    back: Option<String>,
+    #[arg(short = 't', long = "type", value_enum, default_value_t = NewCardType::Normal)]
+    ty: NewCardType,
+    /// Slash separated category path, e.g. "physics/mechanics"
+    #[arg(long)]
+    category: Option<String>,
+    /// Class of an instance, or parent class of a class. Card id or front text
+    #[arg(long)]
+    class: Option<String>,
+    /// Instance an attribute card is about. Card id or front text
+    #[arg(long)]
+    instance: Option<String>,
+    /// Pattern of the attribute to answer, must belong to the class of the instance
+    #[arg(long)]
+    attribute: Option<String>,
+    /// Use another card as the back side. Card id or front text
+    #[arg(long)]
+    back_ref: Option<String>,
+    /// Cards the new card depends on. Card id or front text, can be repeated
+    #[arg(short, long = "dependency")]
+    dependencies: Vec<String>,
+    /// Also add a card asking from back to front. Defaults to the setting of the category
+    #[arg(long)]
+    reverse: Option<bool>,
+}
+
+fn check_class(class: CardId) -> Result<CardId, String> {
+    let is_class =
+        Card::from_id(class).is_some_and(|card| matches!(card.card_type(), AnyType::Class(_)));
+
+    if is_class {
+        Ok(class)
+    } else {
+        Err("the --class card is not a class".to_string())
+    }
+}
+
+/// Creates a card from the command line and returns its id.
+///
+/// Everything that can fail is checked before the card is created, so a failed command
+/// doesn't leave a card behind.
+pub fn add_from_args(args: AddArgs) -> Result<CardId, String> {
+    let category = args
+        .category
+        .as_deref()
+        .map(category_from_path)
+        .unwrap_or_default();
+
+    let class = args
+        .class
+        .as_deref()
+        .map(find_card)
+        .transpose()?
+        .map(check_class)
+        .transpose()?;
+    if class.is_some() && !matches!(args.ty, NewCardType::Class | NewCardType::Instance) {
+        return Err(
+            "--class only applies to classes and instances, e.g. --type instance".to_string(),
+        );
+    }
+    let back_ref = args.back_ref.as_deref().map(find_card).transpose()?;
+    let dependencies = args
+        .dependencies
+        .iter()
+        .map(|dep| find_card(dep))
+        .collect::<Result<Vec<CardId>, String>>()?;
+
+    let back: Option<BackSide> = match (back_ref, args.back) {
+        (Some(reff), _) => Some(BackSide::Card(reff)),
+        (None, Some(back)) => Some(back.into()),
+        (None, None) => None,
+    };
+
+    let reversible =
+        matches!(args.ty, NewCardType::Normal) && matches!(back, Some(BackSide::Text(_)));
+    if args.reverse == Some(true) && !reversible {
+        return Err("only normal cards with a text back side can be reversed".to_string());
+    }
+
+    let id = match args.ty {
+        NewCardType::Normal => {
+            let id = match back {
+                Some(BackSide::Card(reff)) => {
+                    let id = speki_core::add_card(args.front, String::new(), &category);
+                    Card::from_id(id).unwrap().set_ref(reff);
+                    id
+                }
+                Some(back) => speki_core::add_card(args.front, back.to_string(), &category),
+                None => speki_core::add_unfinished(args.front, &category),
+            };
+
+            if args.reverse.unwrap_or_else(|| category_default(&category)) {
+                match add_reverse(id) {
+                    Ok(reverse) => eprintln!("reverse card: {}", reverse.0),
+                    Err(msg) if args.reverse.is_some() => {
+                        eprintln!("failed to add reverse card: {}", msg)
+                    }
+                    Err(_) => {}
+                }
+            }
+
+            id
+        }
+        NewCardType::Class => {
+            let class = ClassCard {
+                name: args.front,
+                back: back.unwrap_or_default(),
+                parent_class: class,
+                is_event: false,
+            };
+            Card::new_class(class, &category).id()
+        }
+        NewCardType::Instance => {
+            let class = class.ok_or("an instance needs a --class")?;
+            let instance = InstanceCard {
+                name: args.front,
+                class,
+            };
+            Card::<InstanceCard>::new(instance, &category).id()
+        }
+        NewCardType::Statement => {
+            let statement = StatementCard { front: args.front };
+            Card::<StatementCard>::new(statement, &category).id()
+        }
+        NewCardType::Event => {
+            let event = EventCard { front: args.front };
+            Card::<EventCard>::new(event, &category).id()
+        }
+        NewCardType::Cloze => {
+            if !is_cloze(&args.front) {
+                return Err("a cloze card needs deletions like {{c1::answer}}".to_string());
+            }
+
+            let ids = add_cloze(&args.front, &category)?;
+            for id in &ids[1..] {
+                for dependency in &dependencies {
+                    speki_core::set_dependency(*id, *dependency);
+                }
+            }
+            ids[0]
+        }
+        NewCardType::Attribute => {
+            let instance = args
+                .instance
+                .as_deref()
+                .map(find_card)
+                .transpose()?
+                .ok_or("an attribute card needs an --instance")?;
+            let class = Card::from_id(instance)
+                .unwrap()
+                .class()
+                .ok_or("the --instance card is not an instance")?;
+            let pattern = args
+                .attribute
+                .ok_or("an attribute card needs an --attribute")?;
+            let attribute = Attribute::load_from_class(class, instance)
+                .into_iter()
+                .find(|attr| attr.pattern().eq_ignore_ascii_case(pattern.trim()))
+                .ok_or_else(|| format!("class has no attribute: {}", pattern))?;
+            let back = back.ok_or("an attribute card needs a back side")?;
+
+            let attr = AttributeCard {
+                attribute: attribute.id,
+                back,
+                instance,
+            };
+            Card::<AttributeCard>::new(attr, &category).id()
+        }
+    };
+
+    for dependency in dependencies {
+        speki_core::set_dependency(id, dependency);
+    }
+
+    Ok(id)
+}
+
 pub async fn add_cards_menu() {
     let items = vec![
         "New cards",
@@ -67,6 +282,7 @@ pub async fn add_cards_menu() {
         "Add wikipedia article",
         "Incremental reading",
         "Import",
+        "Toggle reverse cards in category",
         "exit",
     ];
 
@@ -82,11 +298,22 @@ pub async fn add_cards_menu() {
         2 => add_wikipedia(),
         3 => textstuff(),
         4 => import(),
-        5 => return,
+        5 => toggle_reverse_category(),
+        6 => return,
         _ => panic!(),
     }
 }
 
+fn toggle_reverse_category() {
+    let category = choose_folder();
+    let msg = if toggle_category_default(&category) {
+        "new normal cards in this category will also get a reverse card"
+    } else {
+        "new normal cards in this category won't get a reverse card"
+    };
+    notify(msg);
+}
+
 fn add_wikipedia() {
     use std::fs::File;
     use std::thread;
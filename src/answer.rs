@@ -0,0 +1,266 @@
+use console::style;
+use speki_core::{
+    card::{AnyType, BackSide},
+    common::CardId,
+    reviews::Recall,
+    Card,
+};
+
+/// What a typed answer is compared against.
+pub enum Expected {
+    Text(String),
+    /// Must match the front of the referenced card exactly, apart from normalization.
+    Card(CardId),
+}
+
+impl Expected {
+    /// `None` if the card has no back side that can be typed.
+    pub fn from_card(card: &Card<AnyType>) -> Option<Self> {
+        let back = match card.card_type() {
+            AnyType::Normal(normal) => &normal.back,
+            AnyType::Attribute(attr) => &attr.back,
+            _ => return None,
+        };
+
+        Some(match back {
+            BackSide::Card(id) => Self::Card(*id),
+            back => Self::Text(back.to_string()),
+        })
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Card(id) => Card::from_id(*id)
+                .map(|card| card.print())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn strip_diacritic(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'č' => 'c',
+        'ď' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+        'ğ' => 'g',
+        'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' | 'ı' => 'i',
+        'ł' => 'l',
+        'ñ' | 'ń' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => 'o',
+        'ř' => 'r',
+        'ś' | 'š' | 'ş' => 's',
+        'ť' | 'ţ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' | 'ų' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        c => c,
+    }
+}
+
+/// Lowercased, without diacritics and with whitespace collapsed.
+pub fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(strip_diacritic)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Answers shorter than this, in characters, need an exact match.
+const MIN_FUZZY_LEN: usize = 5;
+
+/// Grade suggested for a typed answer.
+pub fn suggest_grade(typed: &str, expected: &Expected) -> Recall {
+    let exact = matches!(expected, Expected::Card(_));
+    grade_text(typed, &expected.text(), exact)
+}
+
+/// Small typos are tolerated in long text answers, but not in short or numeric ones
+/// where one character makes it a different answer, like "1945" and "1946".
+fn grade_text(typed: &str, target: &str, exact: bool) -> Recall {
+    let typed = normalize(typed);
+    let target = normalize(target);
+
+    if typed.is_empty() {
+        return Recall::None;
+    }
+
+    if typed == target {
+        return Recall::Perfect;
+    }
+
+    let typed: Vec<char> = typed.chars().collect();
+    let target: Vec<char> = target.chars().collect();
+    let len = target.len();
+
+    if exact || len < MIN_FUZZY_LEN || target.iter().any(char::is_ascii_digit) {
+        return Recall::None;
+    }
+
+    let distance = edit_distance(&typed, &target);
+    if distance <= (len / 5).max(1) {
+        Recall::Some
+    } else if distance <= len / 2 {
+        Recall::Late
+    } else {
+        Recall::None
+    }
+}
+
+fn same_char(x: char, y: char) -> bool {
+    let fold = |c: char| strip_diacritic(c.to_lowercase().next().unwrap_or(c));
+    fold(x) == fold(y)
+}
+
+/// The typed answer with wrong characters in red, and the expected answer with missing
+/// characters in green.
+pub fn diff(typed: &str, expected: &Expected) -> (String, String) {
+    let expected = expected.text();
+    let a: Vec<char> = typed.chars().collect();
+    let b: Vec<char> = expected.chars().collect();
+
+    // longest common subsequence table
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if same_char(a[i], b[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut typed_out = String::new();
+    let mut expected_out = String::new();
+
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && same_char(a[i], b[j]) {
+            typed_out.push(a[i]);
+            expected_out.push(b[j]);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            expected_out.push_str(&style(b[j]).green().to_string());
+            j += 1;
+        } else {
+            typed_out.push_str(&style(a[i]).red().to_string());
+            i += 1;
+        }
+    }
+
+    (typed_out, expected_out)
+}
+
+pub fn grade_number(grade: Recall) -> &'static str {
+    match grade {
+        Recall::None => "1",
+        Recall::Late => "2",
+        Recall::Some => "3",
+        Recall::Perfect => "4",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_folds_case_diacritics_and_whitespace() {
+        assert_eq!(normalize("  Crème   Brûlée "), "creme brulee");
+        assert_eq!(normalize("ÅNGSTRÖM"), "angstrom");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(edit_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(edit_distance(&chars("abc"), &chars("abc")), 0);
+        assert_eq!(edit_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(edit_distance(&chars("abc"), &chars("")), 3);
+    }
+
+    #[test]
+    fn exact_answers_are_perfect() {
+        assert_eq!(grade_text("Paris", "paris", false), Recall::Perfect);
+        assert_eq!(
+            grade_text("  crème  brûlée", "creme brulee", false),
+            Recall::Perfect
+        );
+    }
+
+    #[test]
+    fn empty_answers_fail() {
+        assert_eq!(grade_text("", "paris", false), Recall::None);
+        assert_eq!(grade_text("   ", "paris", false), Recall::None);
+    }
+
+    #[test]
+    fn short_and_numeric_answers_need_an_exact_match() {
+        assert_eq!(grade_text("b", "a", false), Recall::None);
+        assert_eq!(grade_text("cat", "car", false), Recall::None);
+        assert_eq!(grade_text("1946", "1945", false), Recall::None);
+        assert_eq!(grade_text("in 1946 ad", "in 1945 ad", false), Recall::None);
+    }
+
+    #[test]
+    fn small_typos_in_long_answers_are_tolerated() {
+        assert_eq!(
+            grade_text("mitochondira", "mitochondria", false),
+            Recall::Some
+        );
+        assert_eq!(grade_text("mitochon", "mitochondria", false), Recall::Late);
+        assert_eq!(grade_text("ribosome", "mitochondria", false), Recall::None);
+    }
+
+    #[test]
+    fn card_references_need_an_exact_match() {
+        assert_eq!(
+            grade_text("mitochondira", "mitochondria", true),
+            Recall::None
+        );
+        assert_eq!(
+            grade_text("mitochondria", "mitochondria", true),
+            Recall::Perfect
+        );
+    }
+
+    #[test]
+    fn diff_marks_wrong_and_missing_characters() {
+        let expected = Expected::Text("hello".to_string());
+        assert_eq!(
+            diff("hello", &expected),
+            ("hello".to_string(), "hello".to_string())
+        );
+
+        let (typed, correct) = diff("hxllo", &expected);
+        assert_eq!(typed, format!("h{}llo", style('x').red()));
+        assert_eq!(correct, format!("h{}llo", style('e').green()));
+
+        let (typed, correct) = diff("Héllo", &expected);
+        assert_eq!(typed, "Héllo");
+        assert_eq!(correct, "hello");
+    }
+}
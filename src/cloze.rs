@@ -0,0 +1,355 @@
+use crate::utils::{notify, save_json, try_load_json};
+use console::style;
+use serde::{Deserialize, Serialize};
+use speki_core::{
+    card::{BackSide, NormalCard},
+    categories::Category,
+    common::CardId,
+    paths::get_share_path,
+    Card,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    sync::Once,
+};
+
+/// A deletion like `{{c1::mitochondria}}` or `{{c1::mitochondria::organelle}}`.
+struct Deletion<'a> {
+    number: u32,
+    answer: &'a str,
+    hint: Option<&'a str>,
+}
+
+enum Part<'a> {
+    Text(&'a str),
+    Deletion(Deletion<'a>),
+}
+
+fn parse_deletion(s: &str) -> Option<Deletion<'_>> {
+    let s = s.strip_prefix('c')?;
+    let (number, rest) = s.split_once("::")?;
+    let number = number.parse().ok()?;
+    let (answer, hint) = match rest.split_once("::") {
+        Some((answer, hint)) => (answer, Some(hint)),
+        None => (rest, None),
+    };
+
+    Some(Deletion {
+        number,
+        answer,
+        hint,
+    })
+}
+
+fn parse(text: &str) -> Vec<Part<'_>> {
+    let mut parts = vec![];
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start..].find("}}") else {
+            break;
+        };
+
+        let inner = &rest[start + 2..start + len];
+        match parse_deletion(inner) {
+            Some(deletion) => {
+                parts.push(Part::Text(&rest[..start]));
+                parts.push(Part::Deletion(deletion));
+            }
+            None => parts.push(Part::Text(&rest[..start + len + 2])),
+        }
+        rest = &rest[start + len + 2..];
+    }
+
+    parts.push(Part::Text(rest));
+    parts
+}
+
+/// The cloze numbers in the text, each of them is reviewed as its own card.
+pub fn numbers(text: &str) -> BTreeSet<u32> {
+    parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Deletion(deletion) => Some(deletion.number),
+            Part::Text(_) => None,
+        })
+        .collect()
+}
+
+pub fn is_cloze(text: &str) -> bool {
+    !numbers(text).is_empty()
+}
+
+/// Text with the deletions of `number` hidden, or highlighted when revealed.
+/// Other deletions are shown as plain text.
+pub fn render(text: &str, number: u32, reveal: bool) -> String {
+    parse(text)
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => text.to_string(),
+            Part::Deletion(deletion) if deletion.number != number => deletion.answer.to_string(),
+            Part::Deletion(deletion) if reveal => style(deletion.answer).bold().green().to_string(),
+            Part::Deletion(deletion) => format!("[{}]", deletion.hint.unwrap_or("...")),
+        })
+        .collect()
+}
+
+/// The hidden text of `number`.
+pub fn answer(text: &str, number: u32) -> String {
+    let answers: Vec<&str> = parse(text)
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Deletion(deletion) if deletion.number == number => Some(deletion.answer),
+            _ => None,
+        })
+        .collect();
+
+    answers.join(", ")
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Cloze {
+    pub text: String,
+    pub number: u32,
+}
+
+impl Cloze {
+    pub fn front(&self) -> String {
+        render(&self.text, self.number, false)
+    }
+
+    pub fn back(&self) -> String {
+        render(&self.text, self.number, true)
+    }
+}
+
+/// Which cards are cloze deletions, and of what text.
+///
+/// Each deletion is stored as a normal card with the blanked text as front and the hidden
+/// text as back, so it works everywhere a normal card does.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(transparent)]
+pub struct Clozes {
+    clozes: BTreeMap<CardId, Cloze>,
+    /// The file didn't parse, saving would lose all the clozes in it.
+    #[serde(skip)]
+    invalid: bool,
+}
+
+impl Clozes {
+    fn path() -> PathBuf {
+        get_share_path().join("clozes")
+    }
+
+    pub fn load() -> Self {
+        try_load_json(&Self::path()).unwrap_or_else(|e| {
+            static REPORTED: Once = Once::new();
+            REPORTED.call_once(|| {
+                eprintln!(
+                    "failed to parse {}: {}, cloze cards show as normal cards",
+                    Self::path().display(),
+                    e
+                )
+            });
+
+            Self {
+                invalid: true,
+                ..Self::default()
+            }
+        })
+    }
+
+    /// Refuses to save over a file that didn't parse.
+    fn check(&self) -> Result<(), String> {
+        if self.invalid {
+            return Err(format!(
+                "{} has errors that need to be fixed first",
+                Self::path().display()
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn save(&self) {
+        match self.check() {
+            Ok(()) => save_json(&Self::path(), self),
+            Err(msg) => notify(format!("not saving clozes, {}", msg)),
+        }
+    }
+
+    pub fn get(card: CardId) -> Option<Cloze> {
+        Self::load().clozes.get(&card).cloned()
+    }
+
+    /// Other cards made from the same text.
+    pub fn siblings(card: CardId) -> Vec<CardId> {
+        let clozes = Self::load();
+        let Some(cloze) = clozes.clozes.get(&card) else {
+            return vec![];
+        };
+
+        clozes
+            .clozes
+            .iter()
+            .filter(|(id, other)| **id != card && other.text == cloze.text)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Creates one card per cloze number in the text.
+pub fn add_cloze(text: &str, category: &Category) -> Result<Vec<CardId>, String> {
+    let mut clozes = Clozes::load();
+    clozes.check()?;
+    let mut ids = vec![];
+
+    for number in numbers(text) {
+        let cloze = Cloze {
+            text: text.to_string(),
+            number,
+        };
+        let front = render(text, number, false);
+        let back = answer(text, number);
+        let id = speki_core::add_card(front, back, category);
+        clozes.clozes.insert(id, cloze);
+        ids.push(id);
+    }
+
+    clozes.save();
+    Ok(ids)
+}
+
+/// Replaces the text of a cloze card and its siblings. Cards are added for new cloze
+/// numbers, and deleted for numbers that are no longer in the text.
+pub fn edit(card: CardId, text: &str) {
+    let mut clozes = Clozes::load();
+    if let Err(msg) = clozes.check() {
+        notify(format!("can't edit the cloze, {}", msg));
+        return;
+    }
+    let Some(old) = clozes.clozes.get(&card).cloned() else {
+        return;
+    };
+    let Some(source) = Card::from_id(card) else {
+        return;
+    };
+
+    let numbers = numbers(text);
+    let siblings: Vec<(CardId, u32)> = clozes
+        .clozes
+        .iter()
+        .filter(|(_, cloze)| cloze.text == old.text)
+        .map(|(id, cloze)| (*id, cloze.number))
+        .collect();
+
+    let mut kept = BTreeSet::new();
+    for (id, number) in siblings {
+        if !numbers.contains(&number) {
+            clozes.clozes.remove(&id);
+            speki_core::delete(id);
+            continue;
+        }
+
+        let cloze = Cloze {
+            text: text.to_string(),
+            number,
+        };
+        if let Some(card) = Card::from_id(id) {
+            card.into_type(NormalCard {
+                front: cloze.front(),
+                back: BackSide::Text(answer(text, number)),
+            });
+        }
+        clozes.clozes.insert(id, cloze);
+        kept.insert(number);
+    }
+
+    for number in numbers.difference(&kept) {
+        let cloze = Cloze {
+            text: text.to_string(),
+            number: *number,
+        };
+        let id = speki_core::add_card(cloze.front(), answer(text, *number), source.category());
+        clozes.clozes.insert(id, cloze);
+    }
+
+    clozes.save();
+}
+
+pub fn on_delete(card: CardId) {
+    let mut clozes = Clozes::load();
+    if clozes.clozes.remove(&card).is_some() {
+        clozes.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "{{c1::Mitochondria}} are the {{c2::powerhouse::role}} of the {{c1::cell}}";
+
+    #[test]
+    fn numbers_are_collected_once() {
+        assert_eq!(numbers(TEXT), BTreeSet::from([1, 2]));
+        assert_eq!(numbers("{{c10::a}} {{c3::b}}"), BTreeSet::from([3, 10]));
+    }
+
+    #[test]
+    fn plain_text_is_not_a_cloze() {
+        assert!(is_cloze(TEXT));
+        assert!(!is_cloze("no deletions here"));
+        assert!(!is_cloze(""));
+    }
+
+    #[test]
+    fn render_hides_only_the_given_number() {
+        assert_eq!(
+            render(TEXT, 1, false),
+            "[...] are the powerhouse of the [...]"
+        );
+        assert_eq!(
+            render(TEXT, 2, false),
+            "Mitochondria are the [role] of the cell"
+        );
+    }
+
+    #[test]
+    fn render_highlights_revealed_answers() {
+        assert_eq!(
+            render(TEXT, 2, true),
+            format!(
+                "Mitochondria are the {} of the cell",
+                style("powerhouse").bold().green()
+            )
+        );
+    }
+
+    #[test]
+    fn answer_joins_all_deletions_of_a_number() {
+        assert_eq!(answer(TEXT, 1), "Mitochondria, cell");
+        assert_eq!(answer(TEXT, 2), "powerhouse");
+        assert_eq!(answer(TEXT, 3), "");
+    }
+
+    #[test]
+    fn malformed_deletions_are_plain_text() {
+        for text in [
+            "{{c1:missing colon}}",
+            "{{x1::not a cloze}}",
+            "{{cx::no number}}",
+            "{{c1::unclosed",
+            "}}{{",
+        ] {
+            assert!(!is_cloze(text), "{}", text);
+            assert_eq!(render(text, 1, false), text);
+        }
+
+        assert_eq!(
+            render("{{c1::a}} {{c1 b}} {{c2::c", 1, false),
+            "[...] {{c1 b}} {{c2::c"
+        );
+    }
+}
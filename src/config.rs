@@ -0,0 +1,158 @@
+use crate::{
+    limits::ReviewLimits,
+    queue::QueueOrder,
+    utils::{notify, save_json},
+};
+use serde::{Deserialize, Serialize};
+use speki_core::paths::config_dir;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, read_to_string},
+    path::PathBuf,
+    sync::Once,
+};
+
+pub const DEFAULT_FILTER: &str =
+    "recall < 0.95 & finished == true & suspended == false & resolved == true & minrecrecall > 0.85 & minrecstab > 50 & lastreview > 0.5 & lapses < 2";
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+fn default_filters() -> BTreeMap<String, String> {
+    BTreeMap::from([(DEFAULT_PROFILE.to_string(), DEFAULT_FILTER.to_string())])
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+/// Matches the `lapses < 2` clause of the default filter, so a card is flagged on the
+/// review that takes it out of the default queue.
+fn default_leech_lapses() -> u32 {
+    2
+}
+
+fn default_new_order() -> QueueOrder {
+    QueueOrder::Topological
+}
+
+/// Settings of the cli, stored as json in the speki config folder.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Config {
+    /// Named filter expressions used to pick cards for review.
+    #[serde(default = "default_filters")]
+    pub filters: BTreeMap<String, String>,
+    #[serde(default = "default_profile")]
+    pub active_filter: String,
+    #[serde(default)]
+    pub limits: ReviewLimits,
+    #[serde(default = "default_new_order")]
+    pub new_order: QueueOrder,
+    #[serde(default)]
+    pub old_order: QueueOrder,
+    /// Seconds to reveal an answer after which a lower grade is suggested.
+    #[serde(default)]
+    pub slow_answer_secs: Option<u64>,
+    /// Type answers before revealing them, for cards with a text back side.
+    #[serde(default)]
+    pub typed_answers: bool,
+    /// Categories where new normal cards also get a reverse card.
+    #[serde(default)]
+    pub reverse_categories: BTreeSet<String>,
+    /// Keys of card view actions by action name, replacing the default keys.
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, Vec<String>>,
+    /// Reveal, grade and pick actions with a single keypress during review.
+    #[serde(default)]
+    pub single_key: bool,
+    /// Lapses after which a card is considered a leech. Filters with a `lapses` clause
+    /// should allow cards below this, or leeches are never reviewed to be flagged.
+    #[serde(default = "default_leech_lapses")]
+    pub leech_lapses: u32,
+    /// Skip the rest of the session for cards related to one that was just shown.
+    #[serde(default)]
+    pub bury_siblings: bool,
+    /// How many levels of weak dependencies to review after a failed card, none if unset.
+    #[serde(default)]
+    pub drill_depth: Option<u32>,
+    /// Loaded from a file that failed to parse.
+    #[serde(skip)]
+    invalid: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            filters: default_filters(),
+            active_filter: default_profile(),
+            limits: ReviewLimits::default(),
+            new_order: default_new_order(),
+            old_order: QueueOrder::default(),
+            slow_answer_secs: None,
+            typed_answers: false,
+            reverse_categories: BTreeSet::new(),
+            keybindings: BTreeMap::new(),
+            single_key: false,
+            leech_lapses: default_leech_lapses(),
+            bury_siblings: false,
+            drill_depth: None,
+            invalid: false,
+        }
+    }
+}
+
+impl Config {
+    fn path() -> PathBuf {
+        config_dir().join("cli.json")
+    }
+
+    /// Falls back to the default settings if the file doesn't parse, those are never saved
+    /// so the file can still be fixed by hand.
+    pub fn load() -> Self {
+        let Ok(s) = read_to_string(Self::path()) else {
+            return Self::default();
+        };
+
+        match serde_json::from_str(&s) {
+            Ok(config) => config,
+            Err(e) => {
+                static REPORTED: Once = Once::new();
+                REPORTED.call_once(|| {
+                    eprintln!(
+                        "failed to parse {}: {}, using default settings",
+                        Self::path().display(),
+                        e
+                    )
+                });
+
+                Self {
+                    invalid: true,
+                    ..Self::default()
+                }
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if self.invalid {
+            notify(format!(
+                "not saving settings, {} has errors that need to be fixed first",
+                Self::path().display()
+            ));
+            return;
+        }
+
+        fs::create_dir_all(config_dir()).unwrap();
+        save_json(&Self::path(), self);
+    }
+
+    /// Filter expression of the given profile, or the active one.
+    pub fn filter(&self, profile: Option<&str>) -> Option<String> {
+        let profile = profile.unwrap_or(&self.active_filter);
+        self.filters.get(profile).cloned()
+    }
+
+    pub fn active_filter(&self) -> String {
+        self.filter(None)
+            .unwrap_or_else(|| DEFAULT_FILTER.to_string())
+    }
+}
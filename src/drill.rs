@@ -0,0 +1,41 @@
+use speki_core::{common::CardId, Card};
+use std::collections::BTreeSet;
+
+/// Dependencies of a failed card with a lower recall than this are reviewed right after it.
+const LOW_RECALL: f32 = 0.9;
+
+/// Dependencies of the card with low recall, and theirs, up to `depth` levels down.
+/// Deepest first, so each card comes after its own weak dependencies.
+/// Cards in `exclude` and unreviewed cards are left out.
+pub fn weak_dependencies(card: CardId, depth: u32, exclude: &BTreeSet<CardId>) -> Vec<CardId> {
+    let mut visited = exclude.clone();
+    visited.insert(card);
+    let mut found = vec![];
+    collect(card, depth, &mut visited, &mut found);
+    found
+}
+
+fn collect(card: CardId, depth: u32, visited: &mut BTreeSet<CardId>, found: &mut Vec<CardId>) {
+    if depth == 0 {
+        return;
+    }
+
+    let Some(card) = Card::from_id(card) else {
+        return;
+    };
+
+    for dependency in card.dependency_ids().iter().copied() {
+        if !visited.insert(dependency) {
+            continue;
+        }
+
+        let weak = Card::from_id(dependency)
+            .and_then(|dep| dep.recall_rate())
+            .is_some_and(|recall| recall < LOW_RECALL);
+
+        if weak {
+            collect(dependency, depth - 1, visited, found);
+            found.push(dependency);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{
+    config::Config,
+    utils::{clear_terminal, get_input_opt, notify, select_item},
+};
+use dialoguer::{theme::ColorfulTheme, Input};
+
+fn match_count(filter: &str) -> usize {
+    speki_core::cards_filtered(filter.to_string()).len()
+}
+
+/// Asks for a filter expression, `None` if left empty.
+pub fn edit_filter(initial: &str) -> Option<String> {
+    let filter: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt("filter")
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .interact_text()
+        .expect("Failed to read input");
+
+    let filter = filter.trim().to_string();
+    if filter.is_empty() {
+        None
+    } else {
+        Some(filter)
+    }
+}
+
+fn manage_profile(name: String) {
+    loop {
+        let mut config = Config::load();
+        let Some(filter) = config.filters.get(&name).cloned() else {
+            return;
+        };
+
+        clear_terminal();
+        println!("{}: {}", name, filter);
+        println!("matches {} cards", match_count(&filter));
+        println!();
+
+        let opts = ["use for review", "edit", "rename", "delete", "go back"];
+        match select_item(&opts) {
+            0 => {
+                config.active_filter = name;
+                config.save();
+                return;
+            }
+            1 => {
+                if let Some(new_filter) = edit_filter(&filter) {
+                    config.filters.insert(name.clone(), new_filter);
+                    config.save();
+                }
+            }
+            2 => {
+                if let Some(new_name) = get_input_opt("new name") {
+                    if config.filters.contains_key(&new_name) {
+                        notify(format!("profile '{}' already exists", new_name));
+                        continue;
+                    }
+
+                    config.filters.remove(&name);
+                    config.filters.insert(new_name.clone(), filter);
+                    if config.active_filter == name {
+                        config.active_filter = new_name.clone();
+                    }
+                    config.save();
+                    return manage_profile(new_name);
+                }
+            }
+            3 => {
+                if config.filters.len() == 1 {
+                    notify("can't delete the last filter profile");
+                    continue;
+                }
+
+                config.filters.remove(&name);
+                if config.active_filter == name {
+                    config.active_filter = config.filters.keys().next().cloned().unwrap();
+                }
+                config.save();
+                return;
+            }
+            4 => return,
+            _ => panic!(),
+        }
+    }
+}
+
+fn create_profile() {
+    let Some(name) = get_input_opt("name of filter profile") else {
+        return;
+    };
+
+    let mut config = Config::load();
+    if config.filters.contains_key(&name) {
+        notify(format!("profile '{}' already exists", name));
+        return;
+    }
+
+    let Some(filter) = edit_filter(&config.active_filter()) else {
+        return;
+    };
+
+    notify(format!("filter matches {} cards", match_count(&filter)));
+    config.filters.insert(name, filter);
+    config.save();
+}
+
+pub fn filter_menu() {
+    loop {
+        clear_terminal();
+        let config = Config::load();
+        let names: Vec<String> = config.filters.keys().cloned().collect();
+
+        let mut opts: Vec<String> = names
+            .iter()
+            .map(|name| {
+                let active = if *name == config.active_filter {
+                    "* "
+                } else {
+                    "  "
+                };
+                format!("{}{}", active, name)
+            })
+            .collect();
+        opts.push("create new".to_string());
+        opts.push("go back".to_string());
+
+        match select_item(&opts) {
+            idx if idx < names.len() => manage_profile(names[idx].clone()),
+            idx if idx == names.len() => create_profile(),
+            _ => return,
+        }
+    }
+}
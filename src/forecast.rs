@@ -0,0 +1,168 @@
+use crate::{
+    config::Config,
+    snooze::Snoozed,
+    stats::{anchored_stability, due_time},
+    utils::{clear_terminal, local_date, notify, DAY},
+};
+use chrono::{Days, Local};
+use clap::ValueEnum;
+use serde_json::json;
+use speki_core::{common::current_time, reviews::Reviews, Card};
+use std::time::Duration;
+
+/// Recall a card is considered due at when the filter doesn't say.
+const DEFAULT_THRESHOLD: f32 = 0.9;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ForecastFormat {
+    /// Bar chart
+    #[default]
+    Text,
+    Json,
+}
+
+/// The value of a clause like `recall < 0.9`.
+fn clause_value(clause: &str, key: &str, op: char) -> Option<f32> {
+    clause
+        .strip_prefix(key)
+        .and_then(|s| s.trim_start().strip_prefix(op))
+        .and_then(|s| s.trim().parse::<f32>().ok())
+}
+
+/// The filter split into the clauses that are projected into the future and the rest.
+struct SplitFilter {
+    /// From `recall < x`, the recall a card becomes due at.
+    threshold: f32,
+    /// From `lastreview > x`, the days a card stays out of review after it's reviewed.
+    min_days: f32,
+    /// Evaluated as of now. Clauses like `minrecrecall` also change over time, but they
+    /// depend on other cards and aren't projected.
+    rest: String,
+}
+
+fn split_filter(filter: &str) -> SplitFilter {
+    let mut split = SplitFilter {
+        threshold: DEFAULT_THRESHOLD,
+        min_days: 0.,
+        rest: String::new(),
+    };
+    let mut rest = vec![];
+
+    for clause in filter.split('&').map(str::trim) {
+        if let Some(recall) = clause_value(clause, "recall", '<') {
+            split.threshold = recall;
+        } else if let Some(days) = clause_value(clause, "lastreview", '>') {
+            split.min_days = days;
+        } else if !clause.is_empty() {
+            rest.push(clause);
+        }
+    }
+
+    split.rest = rest.join(" & ");
+    split
+}
+
+/// Number of cards becoming due on each of the next `days` local calendar days, overdue
+/// cards count for today.
+///
+/// A card is due once its recall drops below the `recall < x` threshold and the
+/// `lastreview > x` days have passed. The other clauses are taken as of now.
+fn due_per_day(filter: &str, days: usize) -> Vec<usize> {
+    let split = split_filter(filter);
+    let cards = if split.rest.is_empty() {
+        Card::load_non_pending(None)
+    } else {
+        Card::load_non_pending(Some(split.rest))
+    };
+
+    let now = current_time();
+    let today = Local::now().date_naive();
+    let snoozed = Snoozed::load();
+    let mut counts = vec![0; days];
+
+    for id in cards {
+        let Some(card) = Card::from_id(id) else {
+            continue;
+        };
+        let Some(last) = Reviews::load(id).0.last().map(|review| review.timestamp) else {
+            continue;
+        };
+
+        let elapsed = now.saturating_sub(last).as_secs_f32() / DAY.as_secs_f32();
+        let stability = anchored_stability(&card, elapsed);
+        let Some(mut due) = due_time(last, stability, split.threshold) else {
+            continue;
+        };
+        if let Ok(wait) = Duration::try_from_secs_f32(split.min_days * DAY.as_secs_f32()) {
+            due = due.max(last + wait);
+        }
+        if let Some(until) = snoozed.until(id) {
+            due = due.max(until);
+        }
+
+        let Some(date) = local_date(due.max(now)) else {
+            continue;
+        };
+        let day = (date - today).num_days() as usize;
+        if let Some(count) = counts.get_mut(day) {
+            *count += 1;
+        }
+    }
+
+    counts
+}
+
+fn date(day: usize) -> String {
+    let date = Local::now().date_naive() + Days::new(day as u64);
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn chart(counts: &[usize]) -> String {
+    let width = 50;
+    let max = counts.iter().copied().max().unwrap_or_default().max(1);
+
+    let mut out = String::new();
+    for (day, count) in counts.iter().enumerate() {
+        let bar = count * width / max;
+        out.push_str(&format!("{} {:>5} {}\n", date(day), count, "#".repeat(bar)));
+    }
+    out.push_str(&format!("\ntotal: {}\n", counts.iter().sum::<usize>()));
+    out
+}
+
+/// Prints how many cards will be due on each of the next `days` days.
+pub fn forecast(days: usize, profile: Option<&str>, format: ForecastFormat) -> Result<(), String> {
+    let config = Config::load();
+    let filter = match profile {
+        Some(profile) => config
+            .filter(Some(profile))
+            .ok_or_else(|| format!("no filter profile named: {}", profile))?,
+        None => config.active_filter(),
+    };
+
+    let counts = due_per_day(&filter, days);
+    match format {
+        ForecastFormat::Text => print!("{}", chart(&counts)),
+        ForecastFormat::Json => {
+            let days: Vec<_> = counts
+                .iter()
+                .enumerate()
+                .map(|(day, count)| json!({"day": day, "date": date(day), "due": count}))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&days).unwrap());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn forecast_menu() {
+    clear_terminal();
+    let config = Config::load();
+    let counts = due_per_day(&config.active_filter(), 14);
+    notify(format!(
+        "cards due in the next 14 days, filter: {}\n\n{}",
+        config.active_filter,
+        chart(&counts)
+    ));
+}
@@ -0,0 +1,236 @@
+use crate::utils::{category_from_path, find_card};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use speki_core::{card::AnyType, common::CardId, Card};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum GraphFormat {
+    #[default]
+    Dot,
+    Mermaid,
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Direction {
+    /// Follow the cards the root depends on
+    Dependencies,
+    /// Follow the cards depending on the root
+    Dependents,
+    #[default]
+    Both,
+}
+
+#[derive(Args, Debug)]
+pub struct GraphArgs {
+    /// Only include cards reachable from this card. Card id or front text
+    #[arg(short, long)]
+    root: Option<String>,
+    /// Max distance from the root
+    #[arg(short, long, requires = "root")]
+    depth: Option<usize>,
+    #[arg(long, value_enum, default_value_t = Direction::Both, requires = "root")]
+    direction: Direction,
+    /// Only include cards within this slash separated category path
+    #[arg(long)]
+    category: Option<String>,
+    /// Only include cards of these types, e.g. "normal,class"
+    #[arg(short = 't', long = "type", value_delimiter = ',')]
+    types: Vec<String>,
+    /// Color nodes by their recall rate
+    #[arg(long)]
+    color_recall: bool,
+    #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+    format: GraphFormat,
+}
+
+struct Node {
+    label: String,
+    ty: String,
+    recall: Option<f32>,
+}
+
+fn recall_color(recall: Option<f32>) -> &'static str {
+    match recall {
+        None => "#d3d3d3",
+        Some(r) if r < 0.5 => "#ff6b6b",
+        Some(r) if r < 0.8 => "#ffa94d",
+        Some(r) if r < 0.95 => "#ffe066",
+        Some(_) => "#8ce99a",
+    }
+}
+
+fn neighbours(card: &Card<AnyType>, direction: Direction) -> Vec<CardId> {
+    let mut ids = vec![];
+    if direction != Direction::Dependents {
+        ids.extend(card.dependency_ids().iter().copied());
+    }
+    if direction != Direction::Dependencies {
+        ids.extend(speki_core::get_cached_dependents(card.id()));
+    }
+    ids
+}
+
+/// Cards reachable from `root` within `depth` steps.
+fn reachable(root: CardId, depth: Option<usize>, direction: Direction) -> BTreeSet<CardId> {
+    let mut seen = BTreeSet::new();
+    let mut queue = VecDeque::from([(root, 0)]);
+
+    while let Some((id, dist)) = queue.pop_front() {
+        if !seen.insert(id) {
+            continue;
+        }
+
+        if depth.is_some_and(|depth| dist >= depth) {
+            continue;
+        }
+
+        if let Some(card) = Card::from_id(id) {
+            for next in neighbours(&card, direction) {
+                queue.push_back((next, dist + 1));
+            }
+        }
+    }
+
+    seen
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', " ")
+}
+
+pub fn graph(args: GraphArgs) -> Result<(), String> {
+    let scope: Option<BTreeSet<CardId>> = match &args.root {
+        Some(root) => {
+            let root = find_card(root)?;
+            Some(reachable(root, args.depth, args.direction))
+        }
+        None => None,
+    };
+
+    let category = args
+        .category
+        .as_deref()
+        .map(|path| category_from_path(path).as_path());
+    let types: Vec<String> = args.types.iter().map(|ty| ty.to_lowercase()).collect();
+
+    let mut nodes: BTreeMap<CardId, Node> = BTreeMap::new();
+    let mut dependencies: BTreeMap<CardId, Vec<CardId>> = BTreeMap::new();
+
+    for card in Card::load_all_cards() {
+        if scope
+            .as_ref()
+            .is_some_and(|scope| !scope.contains(&card.id()))
+        {
+            continue;
+        }
+
+        if category
+            .as_ref()
+            .is_some_and(|cat| !card.category().as_path().starts_with(cat))
+        {
+            continue;
+        }
+
+        let ty = card.card_type().type_name().to_string();
+        if !types.is_empty() && !types.contains(&ty.to_lowercase()) {
+            continue;
+        }
+
+        dependencies.insert(card.id(), card.dependency_ids().iter().copied().collect());
+        nodes.insert(
+            card.id(),
+            Node {
+                label: card.print(),
+                ty,
+                recall: card.recall_rate(),
+            },
+        );
+    }
+
+    let edges: Vec<(CardId, CardId)> = dependencies
+        .into_iter()
+        .flat_map(|(id, deps)| deps.into_iter().map(move |dep| (id, dep)))
+        .filter(|(_, dep)| nodes.contains_key(dep))
+        .collect();
+
+    let out = match args.format {
+        GraphFormat::Dot => {
+            let mut out = String::from("digraph speki {\n");
+            for (id, node) in &nodes {
+                let color = if args.color_recall {
+                    format!(
+                        ", style=filled, fillcolor=\"{}\"",
+                        recall_color(node.recall)
+                    )
+                } else {
+                    String::new()
+                };
+                out.push_str(&format!(
+                    "    \"{}\" [label=\"{}\"{}];\n",
+                    id.0,
+                    escape(&node.label),
+                    color
+                ));
+            }
+            for (from, to) in &edges {
+                out.push_str(&format!("    \"{}\" -> \"{}\";\n", from.0, to.0));
+            }
+            out.push('}');
+            out
+        }
+        GraphFormat::Mermaid => {
+            // mermaid node ids can't contain dashes
+            let node_id = |id: &CardId| format!("n{}", id.0.simple());
+            let mut out = String::from("graph TD\n");
+            for (id, node) in &nodes {
+                out.push_str(&format!(
+                    "    {}[\"{}\"]\n",
+                    node_id(id),
+                    node.label.replace('"', "#quot;").replace('\n', " ")
+                ));
+            }
+            for (from, to) in &edges {
+                out.push_str(&format!("    {} --> {}\n", node_id(from), node_id(to)));
+            }
+            if args.color_recall {
+                for (id, node) in &nodes {
+                    out.push_str(&format!(
+                        "    style {} fill:{}\n",
+                        node_id(id),
+                        recall_color(node.recall)
+                    ));
+                }
+            }
+            out
+        }
+        GraphFormat::Json => {
+            let nodes: Vec<_> = nodes
+                .iter()
+                .map(|(id, node)| {
+                    let mut obj = json!({
+                        "id": id.0.to_string(),
+                        "label": node.label,
+                        "type": node.ty,
+                        "recall": node.recall,
+                    });
+                    if args.color_recall {
+                        obj["color"] = recall_color(node.recall).into();
+                    }
+                    obj
+                })
+                .collect();
+            let edges: Vec<_> = edges
+                .iter()
+                .map(|(from, to)| json!({"from": from.0.to_string(), "to": to.0.to_string()}))
+                .collect();
+            serde_json::to_string_pretty(&json!({"nodes": nodes, "edges": edges})).unwrap()
+        }
+    };
+
+    println!("{}", out);
+    Ok(())
+}
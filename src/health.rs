@@ -0,0 +1,266 @@
+use crate::prune::{find_dangling, Dangling};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use speki_core::{attribute::Attribute, card::AnyType, common::CardId, Card};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum HealthFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+pub enum Problem {
+    /// Cards that (indirectly) depend on themselves.
+    Cycle(Vec<CardId>),
+    /// Attribute card whose instance doesn't belong to the class of the attribute.
+    AttributeClass {
+        card: CardId,
+        instance: CardId,
+        class: CardId,
+    },
+    Dangling(Dangling),
+}
+
+impl Problem {
+    fn is_fixable(&self) -> bool {
+        match self {
+            Self::Dangling(dangling) => dangling.can_fix() && dangling.is_safe_fix(),
+            Self::Cycle(_) | Self::AttributeClass { .. } => false,
+        }
+    }
+
+    /// Reported, but not counted against the health of the cards. These are stale entries
+    /// in the dependents cache of speki-core that the cli has no way to clear.
+    fn is_warning(&self) -> bool {
+        matches!(self, Self::Dangling(dangling) if !dangling.can_fix())
+    }
+
+    fn fix(&self) {
+        if let Self::Dangling(dangling) = self {
+            dangling.fix();
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let mut obj = match self {
+            Self::Cycle(cards) => json!({
+                "kind": "dependency_cycle",
+                "cards": cards.iter().map(|id| id.0.to_string()).collect::<Vec<_>>(),
+            }),
+            Self::AttributeClass {
+                card,
+                instance,
+                class,
+            } => json!({
+                "kind": "attribute_class_mismatch",
+                "card": card.0.to_string(),
+                "instance": instance.0.to_string(),
+                "class": class.0.to_string(),
+            }),
+            Self::Dangling(dangling) => json!({
+                "kind": dangling.kind(),
+                "card": dangling.card().map(|id| id.0.to_string()),
+                "message": dangling.to_string(),
+            }),
+        };
+        obj["fixable"] = self.is_fixable().into();
+        obj["warning"] = self.is_warning().into();
+        obj
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Self::Cycle(cards) => {
+                let names: Vec<String> = cards
+                    .iter()
+                    .map(|id| {
+                        Card::from_id(*id)
+                            .map(|card| card.print())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                format!("dependency cycle: {}", names.join(" -> "))
+            }
+            Self::AttributeClass {
+                card,
+                instance,
+                class,
+            } => {
+                let name = |id: &CardId| {
+                    Card::from_id(*id)
+                        .map(|card| card.print())
+                        .unwrap_or_default()
+                };
+                format!(
+                    "{}: instance '{}' is not of class '{}'",
+                    card.0,
+                    name(instance),
+                    name(class)
+                )
+            }
+            Self::Dangling(dangling) => dangling.to_string(),
+        }
+    }
+}
+
+/// Strongly connected components with more than one card, or a card depending on itself.
+fn find_cycles(graph: &BTreeMap<CardId, Vec<CardId>>) -> Vec<Vec<CardId>> {
+    struct Tarjan<'a> {
+        graph: &'a BTreeMap<CardId, Vec<CardId>>,
+        index: usize,
+        indices: BTreeMap<CardId, usize>,
+        lowlink: BTreeMap<CardId, usize>,
+        stack: Vec<CardId>,
+        on_stack: BTreeSet<CardId>,
+        cycles: Vec<Vec<CardId>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, id: CardId) {
+            self.indices.insert(id, self.index);
+            self.lowlink.insert(id, self.index);
+            self.index += 1;
+            self.stack.push(id);
+            self.on_stack.insert(id);
+
+            let graph = self.graph;
+            for &next in graph.get(&id).into_iter().flatten() {
+                if !graph.contains_key(&next) {
+                    continue;
+                }
+
+                if !self.indices.contains_key(&next) {
+                    self.visit(next);
+                    let low = self.lowlink[&id].min(self.lowlink[&next]);
+                    self.lowlink.insert(id, low);
+                } else if self.on_stack.contains(&next) {
+                    let low = self.lowlink[&id].min(self.indices[&next]);
+                    self.lowlink.insert(id, low);
+                }
+            }
+
+            if self.lowlink[&id] == self.indices[&id] {
+                let mut component = vec![];
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    component.push(member);
+                    if member == id {
+                        break;
+                    }
+                }
+
+                let self_loop = graph[&id].contains(&id);
+                if component.len() > 1 || self_loop {
+                    component.reverse();
+                    self.cycles.push(component);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        graph,
+        index: 0,
+        indices: BTreeMap::new(),
+        lowlink: BTreeMap::new(),
+        stack: vec![],
+        on_stack: BTreeSet::new(),
+        cycles: vec![],
+    };
+
+    for &id in graph.keys() {
+        if !tarjan.indices.contains_key(&id) {
+            tarjan.visit(id);
+        }
+    }
+
+    tarjan.cycles
+}
+
+pub fn find_problems() -> Vec<Problem> {
+    let cards = Card::load_all_cards();
+    let mut problems = vec![];
+
+    let graph: BTreeMap<CardId, Vec<CardId>> = cards
+        .iter()
+        .map(|card| (card.id(), card.dependency_ids().iter().copied().collect()))
+        .collect();
+
+    for cycle in find_cycles(&graph) {
+        problems.push(Problem::Cycle(cycle));
+    }
+
+    for card in &cards {
+        let AnyType::Attribute(attr) = card.card_type() else {
+            continue;
+        };
+
+        let (Some(attribute), Some(instance)) = (
+            Attribute::load(attr.attribute),
+            Card::from_id(attr.instance),
+        ) else {
+            // reported as dangling
+            continue;
+        };
+
+        if !instance.load_belonging_classes().contains(&attribute.class) {
+            problems.push(Problem::AttributeClass {
+                card: card.id(),
+                instance: attr.instance,
+                class: attribute.class,
+            });
+        }
+    }
+
+    problems.extend(find_dangling().into_iter().map(Problem::Dangling));
+    problems
+}
+
+/// Prints the problems found and returns whether the cards are healthy, warnings aside.
+pub fn health(format: HealthFormat, fix: bool) -> bool {
+    let mut problems = find_problems();
+
+    if fix {
+        let fixed = problems.iter().filter(|p| p.is_fixable()).count();
+        for problem in problems.iter().filter(|p| p.is_fixable()) {
+            problem.fix();
+        }
+
+        if fixed > 0 {
+            eprintln!("fixed {} problems", fixed);
+            problems = find_problems();
+        }
+    }
+
+    match format {
+        HealthFormat::Json => {
+            let report: Vec<Value> = problems.iter().map(Problem::to_json).collect();
+            println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        }
+        HealthFormat::Text => {
+            for problem in &problems {
+                let note = if problem.is_fixable() {
+                    " (fixable)"
+                } else if problem.is_warning() {
+                    " (warning)"
+                } else {
+                    ""
+                };
+                println!("{}{}", problem.describe(), note);
+            }
+
+            let warnings = problems.iter().filter(|p| p.is_warning()).count();
+            let errors = problems.len() - warnings;
+            if problems.is_empty() {
+                println!("no problems found");
+            } else {
+                println!();
+                println!("found {} problems and {} warnings", errors, warnings);
+            }
+        }
+    }
+
+    problems.iter().all(Problem::is_warning)
+}
@@ -145,6 +145,22 @@ impl TextProgress {
     }
 }
 
+/// Bookmarks whose text file no longer exists.
+pub fn dangling_bookmarks() -> Vec<PathBuf> {
+    TextProgress::load()
+        .unwrap_or_default()
+        .0
+        .into_keys()
+        .filter(|path| !path.exists())
+        .collect()
+}
+
+pub fn remove_bookmarks(paths: &[PathBuf]) {
+    let mut txts = TextProgress::load().unwrap_or_default();
+    txts.0.retain(|path, _| !paths.contains(path));
+    txts.save();
+}
+
 fn select_text(mut textfiles: Vec<TextFile>) -> TextFile {
     textfiles.sort_by_key(|f| {
         if f.is_finished() {
@@ -0,0 +1,57 @@
+use crate::{config::Config, utils::select_item};
+use console::style;
+use speki_core::{card::AnyType, common::CardId, Card};
+
+/// Cards that lapsed this many times keep failing and probably need rework.
+pub fn is_leech(card: &Card<AnyType>) -> bool {
+    card.lapses() >= Config::load().leech_lapses
+}
+
+/// All leeches, most lapses first.
+pub fn leeches() -> Vec<Card<AnyType>> {
+    let threshold = Config::load().leech_lapses;
+    let mut cards: Vec<Card<AnyType>> = Card::load_all_cards()
+        .into_iter()
+        .filter(|card| card.lapses() >= threshold)
+        .collect();
+
+    cards.sort_by_key(|card| std::cmp::Reverse(card.lapses()));
+    cards
+}
+
+pub enum LeechAction {
+    Suspend,
+    /// Open the card in the editor.
+    Rewrite,
+    NewDependency,
+    Keep,
+}
+
+/// Asks what to do with a card that just lapsed into a leech.
+pub fn ask(card: CardId) -> LeechAction {
+    let card = Card::from_id(card).unwrap();
+    println!(
+        "{}",
+        style(format!(
+            "leech: this card has lapsed {} times, consider changing it",
+            card.lapses()
+        ))
+        .red()
+        .bold()
+    );
+    println!();
+
+    let opts = [
+        "suspend",
+        "rewrite in editor",
+        "add a new dependency",
+        "keep going",
+    ];
+    match select_item(&opts) {
+        0 => LeechAction::Suspend,
+        1 => LeechAction::Rewrite,
+        2 => LeechAction::NewDependency,
+        3 => LeechAction::Keep,
+        _ => panic!(),
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    review::ReviewKind,
+    utils::{load_json, save_json, DAY},
+};
+use serde::{Deserialize, Serialize};
+use speki_core::{common::current_time, paths::get_share_path};
+use std::{path::PathBuf, time::Duration};
+
+/// Caps on how much gets reviewed. `None` means unlimited.
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+pub struct ReviewLimits {
+    #[serde(default)]
+    pub new_per_session: Option<usize>,
+    #[serde(default)]
+    pub old_per_session: Option<usize>,
+    #[serde(default)]
+    pub new_per_day: Option<usize>,
+    #[serde(default)]
+    pub old_per_day: Option<usize>,
+    /// Time budget of a session in minutes.
+    #[serde(default)]
+    pub minutes: Option<u32>,
+}
+
+impl ReviewLimits {
+    fn per_session(&self, kind: ReviewKind) -> Option<usize> {
+        match kind {
+            ReviewKind::Old => self.old_per_session,
+            ReviewKind::Pending => self.new_per_session,
+        }
+    }
+
+    fn per_day(&self, kind: ReviewKind) -> Option<usize> {
+        match kind {
+            ReviewKind::Old => self.old_per_day,
+            ReviewKind::Pending => self.new_per_day,
+        }
+    }
+
+    /// How many cards may still be reviewed in a new session.
+    pub fn remaining(&self, kind: ReviewKind, session_override: Option<usize>) -> Option<usize> {
+        let session = session_override.or(self.per_session(kind));
+        let day = self
+            .per_day(kind)
+            .map(|limit| limit.saturating_sub(DailyCount::load().get(kind)));
+
+        match (session, day) {
+            (Some(session), Some(day)) => Some(session.min(day)),
+            (session, day) => session.or(day),
+        }
+    }
+
+    pub fn time_budget(&self, minutes_override: Option<u32>) -> Option<Duration> {
+        minutes_override
+            .or(self.minutes)
+            .map(|minutes| Duration::from_secs(minutes as u64 * 60))
+    }
+}
+
+/// Days since the unix epoch in local time, so the daily limits reset at local midnight.
+fn today() -> u64 {
+    let offset = chrono::Local::now().offset().local_minus_utc() as i64;
+    (current_time().as_secs() as i64 + offset).div_euclid(DAY.as_secs() as i64) as u64
+}
+
+/// Cards reviewed today, so daily limits hold across sessions.
+#[derive(Serialize, Deserialize, Default)]
+pub struct DailyCount {
+    day: u64,
+    new: usize,
+    old: usize,
+}
+
+impl DailyCount {
+    fn path() -> PathBuf {
+        get_share_path().join("daily_reviews")
+    }
+
+    pub fn load() -> Self {
+        let count: Option<Self> = load_json(&Self::path());
+
+        match count {
+            Some(count) if count.day == today() => count,
+            _ => Self {
+                day: today(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn save(&self) {
+        save_json(&Self::path(), self);
+    }
+
+    pub fn get(&self, kind: ReviewKind) -> usize {
+        match kind {
+            ReviewKind::Old => self.old,
+            ReviewKind::Pending => self.new,
+        }
+    }
+
+    pub fn increment(kind: ReviewKind) {
+        let mut count = Self::load();
+        match kind {
+            ReviewKind::Old => count.old += 1,
+            ReviewKind::Pending => count.new += 1,
+        }
+        count.save();
+    }
+
+    pub fn decrement(kind: ReviewKind) {
+        let mut count = Self::load();
+        match kind {
+            ReviewKind::Old => count.old = count.old.saturating_sub(1),
+            ReviewKind::Pending => count.new = count.new.saturating_sub(1),
+        }
+        count.save();
+    }
+}
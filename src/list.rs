@@ -0,0 +1,192 @@
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use speki_core::{card::AnyType, Card};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ListFormat {
+    /// One json object per line
+    #[default]
+    Jsonl,
+    Csv,
+    /// Human readable table
+    Table,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Id,
+    Front,
+    Back,
+    Type,
+    Category,
+    Recall,
+    Stability,
+    Lapses,
+}
+
+impl Column {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Id,
+            Self::Front,
+            Self::Back,
+            Self::Type,
+            Self::Category,
+            Self::Recall,
+            Self::Stability,
+            Self::Lapses,
+        ]
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Front => "front",
+            Self::Back => "back",
+            Self::Type => "type",
+            Self::Category => "category",
+            Self::Recall => "recall",
+            Self::Stability => "stability",
+            Self::Lapses => "lapses",
+        }
+    }
+
+    fn value(&self, card: &Card<AnyType>) -> Value {
+        match self {
+            Self::Id => card.id().0.to_string().into(),
+            Self::Front => card.print().into(),
+            Self::Back => card
+                .back_side()
+                .map(|back| back.to_string())
+                .unwrap_or_default()
+                .into(),
+            Self::Type => card.card_type().type_name().into(),
+            Self::Category => card
+                .category()
+                .as_path()
+                .to_string_lossy()
+                .to_string()
+                .into(),
+            Self::Recall => card.recall_rate().map(Value::from).unwrap_or(Value::Null),
+            Self::Stability => card.maturity().into(),
+            Self::Lapses => card.lapses().into(),
+        }
+    }
+
+    /// Value as shown in csv and table output.
+    fn text(&self, card: &Card<AnyType>) -> String {
+        match self.value(card) {
+            Value::Null => String::new(),
+            Value::String(s) => s,
+            Value::Number(num) => match self {
+                Self::Recall | Self::Stability => {
+                    format!("{:.2}", num.as_f64().unwrap_or_default())
+                }
+                _ => num.to_string(),
+            },
+            other => other.to_string(),
+        }
+    }
+}
+
+pub fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    let s = s.replace('\n', " ");
+    if s.chars().count() > max {
+        let mut out: String = s.chars().take(max.saturating_sub(1)).collect();
+        out.push('…');
+        out
+    } else {
+        s
+    }
+}
+
+fn load_cards(filter: Option<String>) -> Vec<Card<AnyType>> {
+    match filter {
+        Some(filter) => speki_core::cards_filtered(filter)
+            .into_iter()
+            .filter_map(Card::from_id)
+            .collect(),
+        None => Card::load_all_cards(),
+    }
+}
+
+pub fn list(filter: Option<String>, format: ListFormat, columns: Vec<Column>) {
+    print_cards(&load_cards(filter), format, columns);
+}
+
+pub fn print_cards(cards: &[Card<AnyType>], format: ListFormat, columns: Vec<Column>) {
+    let columns = if columns.is_empty() {
+        Column::all()
+    } else {
+        columns
+    };
+
+    match format {
+        ListFormat::Jsonl => {
+            for card in cards {
+                let obj: Map<String, Value> = columns
+                    .iter()
+                    .map(|col| (col.name().to_string(), col.value(card)))
+                    .collect();
+                println!("{}", Value::Object(obj));
+            }
+        }
+        ListFormat::Csv => {
+            let header: Vec<&str> = columns.iter().map(Column::name).collect();
+            println!("{}", header.join(","));
+            for card in cards {
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|col| csv_escape(&col.text(card)))
+                    .collect();
+                println!("{}", row.join(","));
+            }
+        }
+        ListFormat::Table => {
+            let max_width = 40;
+            let rows: Vec<Vec<String>> = cards
+                .iter()
+                .map(|card| {
+                    columns
+                        .iter()
+                        .map(|col| truncate(&col.text(card), max_width))
+                        .collect()
+                })
+                .collect();
+
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, col)| {
+                    rows.iter()
+                        .map(|row| row[i].chars().count())
+                        .chain(std::iter::once(col.name().len()))
+                        .max()
+                        .unwrap_or_default()
+                })
+                .collect();
+
+            let print_row = |cells: Vec<String>| {
+                let line: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                    .collect();
+                println!("{}", line.join("  ").trim_end());
+            };
+
+            print_row(columns.iter().map(|col| col.name().to_string()).collect());
+            for row in rows {
+                print_row(row);
+            }
+        }
+    }
+}
@@ -1,25 +1,59 @@
-use add_cards::add_cards_menu;
-use clap::Parser;
+use add_cards::{add_cards_menu, add_from_args, AddArgs};
+use clap::{Parser, Subcommand};
 use collections::col_stuff;
+use config::Config;
 use console::style;
 use dialoguer::{theme::ColorfulTheme, Select};
-use incread::inc_path;
+use forecast::{forecast, forecast_menu, ForecastFormat};
+use graph::{graph, GraphArgs};
+use health::{health, HealthFormat};
+use incread::{inc_path, textstuff};
+use leech::leeches;
+use list::{list, print_cards, Column, ListFormat};
 use opener::open;
-use review::{review_menu, view_card};
+use prune::prune;
+use queue::QueueOrder;
+use review::{
+    review_cram, review_menu, review_new, review_old, view_card, ReviewKind, ReviewOptions,
+};
+use scope::Scope;
+use snooze::list_held;
 use speki_core::{
     card::AnyType,
-    categories::Category,
     common::CardId,
     github::{poll_for_token, request_device_code, LoginInfo},
     paths::{config_dir, get_cards_path, get_review_path},
     Card,
 };
-use utils::{clear_terminal, notify, select_from_all_cards};
+use stats::stats;
+use std::path::PathBuf;
+use utils::{clear_terminal, find_card, notify, select_from_all_cards};
 
+mod actions;
 mod add_cards;
+mod answer;
+mod cloze;
 mod collections;
+mod config;
+mod drill;
+mod filters;
+mod forecast;
+mod graph;
+mod health;
 mod incread;
+mod leech;
+mod limits;
+mod list;
+mod prune;
+mod queue;
+mod reverse;
 mod review;
+mod scope;
+mod siblings;
+mod snooze;
+mod stats;
+mod summary;
+mod timing;
 mod unfinished;
 mod utils;
 
@@ -73,6 +107,7 @@ async fn menu() {
             "Inspect files",
             "sync",
             "view card",
+            "forecast",
             sign,
         ];
 
@@ -94,10 +129,11 @@ async fn menu() {
             }
             5 => {
                 if let Some(card) = select_from_all_cards() {
-                    view_card(card, false);
+                    view_card(card, None);
                 }
             }
-            6 => match login.take() {
+            6 => forecast_menu(),
+            7 => match login.take() {
                 Some(login) => login.delete_login(),
                 None => login = Some(authenticate()),
             },
@@ -152,24 +188,150 @@ fn print_card_info(id: CardId) {
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    #[arg(short, long)]
-    add: Option<String>,
-    #[arg(short, long)]
-    filter: Option<String>,
-    #[arg(short, long)]
-    concept: Option<String>,
-    #[arg(short, long)]
-    list: bool,
-    #[arg(short, long)]
-    graph: bool,
-    #[arg(short, long)]
-    prune: bool,
-    #[arg(long)]
-    debug: bool,
-    #[arg(long)]
-    recall: Option<String>,
-    #[arg(long)]
-    healthcheck: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Add a card and print its id
+    Add(AddArgs),
+    /// List cards, optionally matching a filter expression
+    List {
+        /// Filter expression, e.g. "recall < 0.9 & suspended == false"
+        #[arg(short, long)]
+        filter: Option<String>,
+        #[arg(long, value_enum, default_value_t = ListFormat::Jsonl)]
+        format: ListFormat,
+        /// Comma separated list of columns to print, defaults to all
+        #[arg(short, long, value_enum, value_delimiter = ',')]
+        columns: Vec<Column>,
+    },
+    /// List cards that keep failing, most lapses first
+    Leeches {
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+        /// Comma separated list of columns to print, defaults to all
+        #[arg(short, long, value_enum, value_delimiter = ',')]
+        columns: Vec<Column>,
+    },
+    /// List suspended and snoozed cards
+    Suspended {
+        #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+        format: ListFormat,
+    },
+    /// Export the dependency graph, or a part of it
+    Graph(GraphArgs),
+    /// Find and remove references to cards and texts that no longer exist
+    Prune {
+        /// Only print what would be pruned
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Show review history and recall statistics of a card
+    Stats {
+        /// Card id or front text
+        card: String,
+    },
+    /// Check the cards for problems, exits with an error code if any are found. Stale
+    /// entries in the dependents cache are only reported as warnings
+    Health {
+        #[arg(long, value_enum, default_value_t = HealthFormat::Text)]
+        format: HealthFormat,
+        /// Fix the problems that can be fixed without changing card content
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Show how many cards become due on each of the next days
+    Forecast {
+        /// Number of days to forecast
+        #[arg(short, long, default_value_t = 14)]
+        days: usize,
+        /// Name of the filter profile to use, defaults to the active one
+        #[arg(short, long)]
+        profile: Option<String>,
+        #[arg(long, value_enum, default_value_t = ForecastFormat::Text)]
+        format: ForecastFormat,
+    },
+    /// Review cards
+    Review {
+        #[arg(value_enum, default_value_t = ReviewKind::Old)]
+        kind: ReviewKind,
+        /// Name of the filter profile to use, defaults to the active one
+        #[arg(short, long, conflicts_with = "filter")]
+        profile: Option<String>,
+        /// Filter expression to use instead of a profile
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Only review cards in this category, like "physics/mechanics", can be repeated
+        #[arg(long)]
+        category: Vec<String>,
+        /// Only review cards in this collection, can be repeated
+        #[arg(long)]
+        collection: Vec<String>,
+        /// Max cards to review in this session
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Stop the session after this many minutes
+        #[arg(short, long)]
+        minutes: Option<u32>,
+        /// Order of the cards, defaults to the one in the config
+        #[arg(short, long, value_enum)]
+        order: Option<QueueOrder>,
+        /// Write a summary of the session as json to this file
+        #[arg(long)]
+        summary_json: Option<PathBuf>,
+        /// Type answers before revealing them
+        #[arg(long)]
+        typed: bool,
+        /// Reveal with space, grade and pick actions with a single keypress
+        #[arg(long)]
+        single_key: bool,
+        /// Skip related cards for the rest of the session once one of them is shown
+        #[arg(long)]
+        bury: bool,
+        /// After a failed card, review its dependencies with low recall up to this many levels down
+        #[arg(long, value_name = "DEPTH")]
+        drill: Option<u32>,
+    },
+    /// Go through cards without affecting when they're scheduled, failed cards come back until passed
+    Cram {
+        /// Filter expression, all cards if not set
+        #[arg(short, long)]
+        filter: Option<String>,
+        /// Only cram cards in this category, like "physics/mechanics", can be repeated
+        #[arg(long)]
+        category: Vec<String>,
+        /// Only cram cards in this collection, can be repeated
+        #[arg(long)]
+        collection: Vec<String>,
+        /// Max cards to cram in this session
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Order of the cards, random by default
+        #[arg(short, long, value_enum)]
+        order: Option<QueueOrder>,
+        /// Type answers before revealing them
+        #[arg(long)]
+        typed: bool,
+        /// Reveal with space, grade and pick actions with a single keypress
+        #[arg(long)]
+        single_key: bool,
+    },
+    /// Continue incremental reading
+    Read,
+    /// Sync collections with github
+    Sync,
+    /// View a card
+    View {
+        /// Card id or front text
+        card: String,
+    },
+    #[command(hide = true)]
+    Debug,
 }
 
 pub fn authenticate() -> LoginInfo {
@@ -186,34 +348,155 @@ pub fn authenticate() -> LoginInfo {
 async fn main() {
     let cli = Cli::parse();
 
-    if cli.add.is_some() {
-        let s = cli.add.unwrap();
-        let category = Category::default();
+    let Some(command) = cli.command else {
+        menu().await;
+        return;
+    };
 
-        if let Some((front, back)) = s.split_once(";") {
-            speki_core::add_card(front.to_string(), back.to_string(), &category);
-        } else {
-            speki_core::add_unfinished(s, &category);
+    match command {
+        Command::Add(args) => match add_from_args(args) {
+            Ok(id) => println!("{}", id.0),
+            Err(msg) => {
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            }
+        },
+        Command::List {
+            filter,
+            format,
+            columns,
+        } => list(filter, format, columns),
+        Command::Leeches { format, columns } => print_cards(&leeches(), format, columns),
+        Command::Suspended { format } => list_held(format),
+        Command::Graph(args) => {
+            if let Err(msg) = graph(args) {
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        Command::Prune { dry_run, yes } => prune(dry_run, yes),
+        Command::Stats { card } => {
+            if let Err(msg) = stats(&card) {
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        Command::Health { format, fix } => {
+            if !health(format, fix) {
+                std::process::exit(1);
+            }
+        }
+        Command::Forecast {
+            days,
+            profile,
+            format,
+        } => {
+            if let Err(msg) = forecast(days, profile.as_deref(), format) {
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            }
+        }
+        Command::Review {
+            kind,
+            profile,
+            filter,
+            category,
+            collection,
+            limit,
+            minutes,
+            order,
+            summary_json,
+            typed,
+            single_key,
+            bury,
+            drill,
+        } => {
+            let config = Config::load();
+            let filter = match (filter, profile) {
+                (Some(filter), _) => filter,
+                (None, Some(profile)) => match config.filter(Some(&profile)) {
+                    Some(filter) => filter,
+                    None => {
+                        eprintln!("no filter profile named: {}", profile);
+                        std::process::exit(1);
+                    }
+                },
+                (None, None) => config.active_filter(),
+            };
+
+            let scope = match Scope::from_args(&category, &collection) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let opts = ReviewOptions {
+                limit,
+                minutes,
+                order,
+                summary_json,
+                typed,
+                single_key,
+                scope,
+                bury,
+                drill_depth: drill,
+            };
+            match kind {
+                ReviewKind::Old => review_old(filter, &opts),
+                ReviewKind::Pending => review_new(filter, &opts),
+            }
+        }
+        Command::Cram {
+            filter,
+            category,
+            collection,
+            limit,
+            order,
+            typed,
+            single_key,
+        } => {
+            let scope = match Scope::from_args(&category, &collection) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
+            let opts = ReviewOptions {
+                limit,
+                order,
+                typed,
+                single_key,
+                scope,
+                ..Default::default()
+            };
+            review_cram(filter, &opts);
+        }
+        Command::Read => textstuff(),
+        Command::Sync => match LoginInfo::load() {
+            Some(login) => {
+                speki_core::github::sync(&login);
+            }
+            None => {
+                eprintln!("not signed in");
+                std::process::exit(1);
+            }
+        },
+        Command::View { card } => match find_card(&card) {
+            Ok(id) => {
+                let _ = view_card(id, None);
+            }
+            Err(msg) => {
+                eprintln!("{}", msg);
+                std::process::exit(1);
+            }
+        },
+        Command::Debug => {
+            //speki_core::fetch_repos();
+            // speki_core::categories::Category::load_all();
         }
-    } else if cli.list {
-        dbg!(speki_core::load_cards());
-    } else if cli.graph {
-        println!("{}", speki_core::as_graph());
-    } else if cli.prune {
-        todo!()
-    } else if cli.debug {
-        //speki_core::fetch_repos();
-        // speki_core::categories::Category::load_all();
-    } else if cli.recall.is_some() {
-        let id = cli.recall.unwrap();
-        let id: uuid::Uuid = id.parse().unwrap();
-        let id = CardId(id);
-        let x = speki_core::Card::from_id(id).unwrap().recall_rate();
-        dbg!(x);
-    } else if cli.concept.is_some() {
-    } else if cli.healthcheck {
-        speki_core::health_check();
-    } else {
-        menu().await;
     }
 }
@@ -0,0 +1,297 @@
+use crate::{
+    incread::{dangling_bookmarks, remove_bookmarks},
+    timing::ResponseLog,
+};
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use speki_core::{
+    attribute::Attribute,
+    card::{AnyType, BackSide, UnfinishedCard},
+    common::CardId,
+    paths::get_review_path,
+    Card,
+};
+use std::{collections::BTreeSet, fmt, fs, path::PathBuf};
+
+/// Reference to data that no longer exists.
+pub enum Dangling {
+    Dependency {
+        card: CardId,
+        dependency: CardId,
+    },
+    Dependent {
+        card: CardId,
+        dependent: CardId,
+    },
+    /// Instance whose class is gone.
+    Class {
+        card: CardId,
+        class: CardId,
+    },
+    ParentClass {
+        card: CardId,
+        parent: CardId,
+    },
+    /// Attribute card whose instance is gone.
+    Instance {
+        card: CardId,
+        instance: CardId,
+    },
+    Attribute {
+        card: CardId,
+    },
+    BackRef {
+        card: CardId,
+        reff: CardId,
+    },
+    ReviewLog {
+        path: PathBuf,
+    },
+    Bookmark {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for Dangling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dependency { card, dependency } => {
+                write!(f, "{}: missing dependency {}", card.0, dependency.0)
+            }
+            Self::Dependent { card, dependent } => {
+                write!(f, "{}: missing dependent {}", card.0, dependent.0)
+            }
+            Self::Class { card, class } => write!(f, "{}: missing class {}", card.0, class.0),
+            Self::ParentClass { card, parent } => {
+                write!(f, "{}: missing parent class {}", card.0, parent.0)
+            }
+            Self::Instance { card, instance } => {
+                write!(f, "{}: missing instance {}", card.0, instance.0)
+            }
+            Self::Attribute { card } => write!(f, "{}: missing attribute", card.0),
+            Self::BackRef { card, reff } => {
+                write!(f, "{}: back side refers to missing card {}", card.0, reff.0)
+            }
+            Self::ReviewLog { path } => {
+                write!(f, "review log for missing card: {}", path.display())
+            }
+            Self::Bookmark { path } => {
+                write!(f, "bookmark for missing text: {}", path.display())
+            }
+        }
+    }
+}
+
+impl Dangling {
+    /// Whether the cli can fix this at all.
+    /// A missing dependent is a stale entry in the dependents cache of speki-core, and
+    /// removing the link would have to load the missing card.
+    pub fn can_fix(&self) -> bool {
+        !matches!(self, Self::Dependent { .. })
+    }
+
+    /// Whether fixing this only removes the dangling data, without touching the content
+    /// of any existing card.
+    pub fn is_safe_fix(&self) -> bool {
+        match self {
+            Self::Dependency { .. }
+            | Self::Dependent { .. }
+            | Self::ParentClass { .. }
+            | Self::ReviewLog { .. }
+            | Self::Bookmark { .. } => true,
+            Self::Class { .. }
+            | Self::Instance { .. }
+            | Self::Attribute { .. }
+            | Self::BackRef { .. } => false,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Dependency { .. } => "missing_dependency",
+            Self::Dependent { .. } => "missing_dependent",
+            Self::Class { .. } => "missing_class",
+            Self::ParentClass { .. } => "missing_parent_class",
+            Self::Instance { .. } => "missing_instance",
+            Self::Attribute { .. } => "missing_attribute",
+            Self::BackRef { .. } => "broken_back_ref",
+            Self::ReviewLog { .. } => "orphaned_review_log",
+            Self::Bookmark { .. } => "orphaned_bookmark",
+        }
+    }
+
+    /// The card the problem belongs to, if any.
+    pub fn card(&self) -> Option<CardId> {
+        match self {
+            Self::Dependency { card, .. }
+            | Self::Dependent { card, .. }
+            | Self::Class { card, .. }
+            | Self::ParentClass { card, .. }
+            | Self::Instance { card, .. }
+            | Self::Attribute { card }
+            | Self::BackRef { card, .. } => Some(*card),
+            Self::ReviewLog { .. } | Self::Bookmark { .. } => None,
+        }
+    }
+
+    pub fn fix(&self) {
+        match self {
+            Self::Dependency { card, dependency } => {
+                speki_core::remove_dependency(*card, *dependency);
+            }
+            Self::Dependent { .. } => {}
+            Self::ParentClass { card, .. } => {
+                let card = Card::from_id(*card).unwrap();
+                if let AnyType::Class(class) = card.card_type() {
+                    let mut class = class.clone();
+                    class.parent_class = None;
+                    card.into_type(class);
+                }
+            }
+            // Without the referenced data the card can't be answered anymore,
+            // so it goes back to being unfinished.
+            Self::Class { card, .. }
+            | Self::Instance { card, .. }
+            | Self::Attribute { card }
+            | Self::BackRef { card, .. } => {
+                let card = Card::from_id(*card).unwrap();
+                let front = card.print();
+                card.into_type(UnfinishedCard { front });
+            }
+            Self::ReviewLog { path } => {
+                let _ = fs::remove_file(path);
+            }
+            Self::Bookmark { path } => remove_bookmarks(&[path.clone()]),
+        }
+    }
+}
+
+fn dangling_review_logs(ids: &BTreeSet<CardId>) -> Vec<PathBuf> {
+    [get_review_path(), ResponseLog::dir()]
+        .into_iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<uuid::Uuid>().ok())
+                .is_some_and(|id| !ids.contains(&CardId(id)))
+        })
+        .collect()
+}
+
+pub fn find_dangling() -> Vec<Dangling> {
+    let cards = Card::load_all_cards();
+    let ids: BTreeSet<CardId> = cards.iter().map(|card| card.id()).collect();
+    let missing = |id: &CardId| !ids.contains(id);
+
+    let mut found = vec![];
+
+    for card in &cards {
+        let id = card.id();
+
+        for dependency in card.dependency_ids().iter().filter(|dep| missing(dep)) {
+            found.push(Dangling::Dependency {
+                card: id,
+                dependency: *dependency,
+            });
+        }
+
+        for dependent in speki_core::get_cached_dependents(id)
+            .into_iter()
+            .filter(|dep| missing(dep))
+        {
+            found.push(Dangling::Dependent {
+                card: id,
+                dependent,
+            });
+        }
+
+        match card.card_type() {
+            AnyType::Instance(ty) if missing(&ty.class) => {
+                found.push(Dangling::Class {
+                    card: id,
+                    class: ty.class,
+                });
+            }
+            AnyType::Class(class) => {
+                if let Some(parent) = class.parent_class.filter(|parent| missing(parent)) {
+                    found.push(Dangling::ParentClass { card: id, parent });
+                }
+            }
+            AnyType::Attribute(attr) => {
+                if missing(&attr.instance) {
+                    found.push(Dangling::Instance {
+                        card: id,
+                        instance: attr.instance,
+                    });
+                } else if Attribute::load(attr.attribute).is_none() {
+                    found.push(Dangling::Attribute { card: id });
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(BackSide::Card(reff)) = card.back_side() {
+            if missing(reff) {
+                found.push(Dangling::BackRef {
+                    card: id,
+                    reff: *reff,
+                });
+            }
+        }
+    }
+
+    for path in dangling_review_logs(&ids) {
+        found.push(Dangling::ReviewLog { path });
+    }
+
+    for path in dangling_bookmarks() {
+        found.push(Dangling::Bookmark { path });
+    }
+
+    found
+}
+
+pub fn prune(dry_run: bool, yes: bool) {
+    let dangling = find_dangling();
+
+    if dangling.is_empty() {
+        println!("nothing to prune");
+        return;
+    }
+
+    for item in &dangling {
+        println!("{}", item);
+    }
+    println!();
+    println!("found {} dangling references", dangling.len());
+
+    if dry_run {
+        return;
+    }
+
+    let confirmed = yes
+        || Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("fix them?")
+            .default(false)
+            .interact()
+            .unwrap();
+
+    if confirmed {
+        let (fixable, unfixable): (Vec<&Dangling>, Vec<&Dangling>) =
+            dangling.iter().partition(|item| item.can_fix());
+        for item in &fixable {
+            item.fix();
+        }
+        println!("pruned {} dangling references", fixable.len());
+
+        if !unfixable.is_empty() {
+            println!(
+                "{} missing dependents are stale entries in the dependents cache and were left as is",
+                unfixable.len()
+            );
+        }
+    }
+}
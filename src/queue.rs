@@ -0,0 +1,134 @@
+use clap::ValueEnum;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use speki_core::{common::CardId, Card};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Order in which the cards of a review session are shown.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum QueueOrder {
+    #[default]
+    Random,
+    /// Dependencies before their dependents
+    Topological,
+    /// Cards with the lowest recall rate first
+    #[serde(alias = "lowestrecall")]
+    LowestRecall,
+    /// Alternate between categories
+    Interleave,
+}
+
+impl QueueOrder {
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::Random,
+            Self::Topological,
+            Self::LowestRecall,
+            Self::Interleave,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Random => "random",
+            Self::Topological => "dependencies first",
+            Self::LowestRecall => "lowest recall first",
+            Self::Interleave => "interleave categories",
+        }
+    }
+
+    pub fn apply(&self, mut cards: Vec<CardId>) -> Vec<CardId> {
+        cards.shuffle(&mut thread_rng());
+
+        match self {
+            Self::Random => cards,
+            Self::Topological => topological(cards),
+            Self::LowestRecall => lowest_recall(cards),
+            Self::Interleave => interleave(cards),
+        }
+    }
+}
+
+/// Kahn's algorithm, only considering dependencies among the given cards.
+/// Cards caught in a dependency cycle are put at the end.
+fn topological(cards: Vec<CardId>) -> Vec<CardId> {
+    let set: BTreeSet<CardId> = cards.iter().copied().collect();
+    let mut remaining_deps: BTreeMap<CardId, usize> = BTreeMap::new();
+    let mut dependents: BTreeMap<CardId, Vec<CardId>> = BTreeMap::new();
+
+    for id in &cards {
+        let deps: Vec<CardId> = Card::from_id(*id)
+            .map(|card| card.dependency_ids().iter().copied().collect())
+            .unwrap_or_default();
+        let deps: Vec<CardId> = deps.into_iter().filter(|dep| set.contains(dep)).collect();
+
+        remaining_deps.insert(*id, deps.len());
+        for dep in deps {
+            dependents.entry(dep).or_default().push(*id);
+        }
+    }
+
+    let mut ready: VecDeque<CardId> = cards
+        .iter()
+        .copied()
+        .filter(|id| remaining_deps[id] == 0)
+        .collect();
+    let mut sorted = Vec::with_capacity(cards.len());
+
+    while let Some(id) = ready.pop_front() {
+        sorted.push(id);
+        for dependent in dependents.get(&id).into_iter().flatten() {
+            let count = remaining_deps.get_mut(dependent).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                ready.push_back(*dependent);
+            }
+        }
+    }
+
+    let placed: BTreeSet<CardId> = sorted.iter().copied().collect();
+    sorted.extend(cards.into_iter().filter(|id| !placed.contains(id)));
+    sorted
+}
+
+fn lowest_recall(cards: Vec<CardId>) -> Vec<CardId> {
+    let mut with_recall: Vec<(CardId, f32)> = cards
+        .into_iter()
+        .map(|id| {
+            let recall = Card::from_id(id)
+                .and_then(|card| card.recall_rate())
+                .unwrap_or_default();
+            (id, recall)
+        })
+        .collect();
+
+    with_recall.sort_by(|a, b| a.1.total_cmp(&b.1));
+    with_recall.into_iter().map(|(id, _)| id).collect()
+}
+
+/// Round robin over the categories, so cards of the same category aren't shown in a row.
+fn interleave(cards: Vec<CardId>) -> Vec<CardId> {
+    let mut groups: BTreeMap<String, VecDeque<CardId>> = BTreeMap::new();
+
+    for id in cards {
+        let category = Card::from_id(id)
+            .map(|card| card.category().as_path().to_string_lossy().to_string())
+            .unwrap_or_default();
+        groups.entry(category).or_default().push_back(id);
+    }
+
+    let mut groups: Vec<VecDeque<CardId>> = groups.into_values().collect();
+    groups.shuffle(&mut thread_rng());
+
+    let mut out = vec![];
+    while groups.iter().any(|group| !group.is_empty()) {
+        for group in groups.iter_mut() {
+            if let Some(id) = group.pop_front() {
+                out.push(id);
+            }
+        }
+    }
+
+    out
+}
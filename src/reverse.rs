@@ -0,0 +1,183 @@
+use crate::{
+    cloze::Clozes,
+    config::Config,
+    utils::{category_from_path, notify, save_json, try_load_json},
+};
+use serde::{Deserialize, Serialize};
+use speki_core::{
+    card::{AnyType, BackSide, NormalCard},
+    categories::Category,
+    common::CardId,
+    paths::get_share_path,
+    Card,
+};
+use std::{collections::BTreeMap, path::PathBuf, sync::Once};
+
+/// Normal cards that also have a card asking from back to front, by source card.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(transparent)]
+pub struct Reverses {
+    reverses: BTreeMap<CardId, CardId>,
+    /// The file didn't parse, saving would lose all the reverses in it.
+    #[serde(skip)]
+    invalid: bool,
+}
+
+impl Reverses {
+    fn path() -> PathBuf {
+        get_share_path().join("reverses")
+    }
+
+    pub fn load() -> Self {
+        try_load_json(&Self::path()).unwrap_or_else(|e| {
+            static REPORTED: Once = Once::new();
+            REPORTED.call_once(|| {
+                eprintln!(
+                    "failed to parse {}: {}, reverse cards are not kept in sync",
+                    Self::path().display(),
+                    e
+                )
+            });
+
+            Self {
+                invalid: true,
+                ..Self::default()
+            }
+        })
+    }
+
+    fn save(&self) {
+        if self.invalid {
+            notify(format!(
+                "not saving reverses, {} has errors that need to be fixed first",
+                Self::path().display()
+            ));
+            return;
+        }
+
+        save_json(&Self::path(), self);
+    }
+
+    pub fn reverse_of(card: CardId) -> Option<CardId> {
+        Self::load().reverses.get(&card).copied()
+    }
+
+    pub fn source_of(card: CardId) -> Option<CardId> {
+        Self::load()
+            .reverses
+            .into_iter()
+            .find(|(_, reverse)| *reverse == card)
+            .map(|(source, _)| source)
+    }
+}
+
+/// Front and back of a normal card with a text back side.
+fn sides(card: &Card<AnyType>) -> Option<(String, String)> {
+    match card.card_type() {
+        AnyType::Normal(NormalCard {
+            front,
+            back: BackSide::Text(back),
+        }) => Some((front.clone(), back.clone())),
+        _ => None,
+    }
+}
+
+/// Whether cards added to the category get a reverse card by default.
+pub fn category_default(category: &Category) -> bool {
+    let path = category.as_path();
+    Config::load()
+        .reverse_categories
+        .iter()
+        .any(|cat| path.starts_with(category_from_path(cat).as_path()))
+}
+
+pub fn toggle_category_default(category: &Category) -> bool {
+    let path = category
+        .as_path()
+        .strip_prefix(Category::default().as_path())
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut config = Config::load();
+    let enabled = if config.reverse_categories.remove(&path) {
+        false
+    } else {
+        config.reverse_categories.insert(path);
+        true
+    };
+    config.save();
+    enabled
+}
+
+/// Creates the back to front card of a normal card.
+pub fn add_reverse(source: CardId) -> Result<CardId, &'static str> {
+    if let Some(reverse) = Reverses::reverse_of(source) {
+        return Ok(reverse);
+    }
+
+    if Reverses::source_of(source).is_some() {
+        return Err("card is already a reverse card");
+    }
+
+    if Clozes::get(source).is_some() {
+        return Err("cloze cards can't be reversed");
+    }
+
+    let mut reverses = Reverses::load();
+    if reverses.invalid {
+        return Err("the reverses file has errors that need to be fixed first");
+    }
+
+    let card = Card::from_id(source).unwrap();
+    let (front, back) =
+        sides(&card).ok_or("only normal cards with a text answer can be reversed")?;
+
+    let reverse = speki_core::add_card(back, front, card.category());
+    reverses.reverses.insert(source, reverse);
+    reverses.save();
+    Ok(reverse)
+}
+
+pub fn remove_reverse(source: CardId) {
+    let mut reverses = Reverses::load();
+    if let Some(reverse) = reverses.reverses.remove(&source) {
+        speki_core::delete(reverse);
+        reverses.save();
+    }
+}
+
+/// Keeps a card and its reverse in sync after one of them was edited.
+pub fn sync(card: CardId) {
+    let (other, edited) = match (Reverses::reverse_of(card), Reverses::source_of(card)) {
+        (Some(reverse), _) => (reverse, card),
+        (None, Some(source)) => (source, card),
+        (None, None) => return,
+    };
+
+    let Some(edited) = Card::from_id(edited) else {
+        return;
+    };
+    let Some(other) = Card::from_id(other) else {
+        return;
+    };
+
+    if let Some((front, back)) = sides(&edited) {
+        if sides(&other) != Some((back.clone(), front.clone())) {
+            other.into_type(NormalCard {
+                front: back,
+                back: BackSide::Text(front),
+            });
+        }
+    }
+}
+
+/// Deletes the reverse together with its source card.
+pub fn on_delete(card: CardId) {
+    if Reverses::reverse_of(card).is_some() {
+        remove_reverse(card);
+    } else if let Some(source) = Reverses::source_of(card) {
+        let mut reverses = Reverses::load();
+        reverses.reverses.remove(&source);
+        reverses.save();
+    }
+}
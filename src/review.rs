@@ -1,175 +1,199 @@
 use crate::{
+    actions::{Action, CardAction, Keymap, ReviewAction},
     add_cards::add_card,
+    answer::{self, grade_number, Expected},
+    cloze::{self, is_cloze, Clozes},
+    config::Config,
+    drill::weak_dependencies,
+    filters::{edit_filter, filter_menu},
+    leech::{self, LeechAction},
+    limits::DailyCount,
     print_card_info,
+    queue::QueueOrder,
+    reverse::{self, Reverses},
+    scope::Scope,
+    siblings::siblings_among,
+    snooze::Snoozed,
+    summary::{CardSnapshot, SessionSummary},
+    timing::{ResponseLog, ResponseTime},
     utils::{
-        clear_terminal, get_input, notify, select_from_all_cards, select_from_all_class_cards,
-        select_from_all_instance_cards, select_from_attributes, select_from_cards,
-        select_from_class_attributes, select_from_subclass_cards,
+        clear_terminal, get_input, get_input_opt, notify, read_key, select_from_all_cards,
+        select_from_all_class_cards, select_from_all_instance_cards, select_from_attributes,
+        select_from_cards, select_from_class_attributes, select_from_subclass_cards, select_item,
     },
 };
+use clap::ValueEnum;
+use console::{style, Key};
 use dialoguer::{theme::ColorfulTheme, Input, Select};
-use rand::prelude::*;
 use speki_core::{
     attribute::Attribute,
     card::{AnyType, AttributeCard, BackSide, ClassCard, EventCard, InstanceCard, StatementCard},
-    common::CardId,
-    reviews::Recall,
+    common::{current_time, CardId},
+    reviews::{Recall, Reviews},
     Card,
 };
-use std::{ops::ControlFlow, str::FromStr};
-
-fn review_help() -> &'static str {
-    r#"
-
-possible commands:
-
-1 =>        failed to recall backside, where the backside info seems new to you
-2 =>        failed ot recall backside but the information was familiar to you when reading it
-3 =>        successfully recalled backside after some thinking
-4 =>        successfully recalled backside without hesitation
-skip | s => skip card
-y =>        add new dependency, from cards in your collections
-t =>        add new dependent, from cards in your collections
-Y =>        add new dependency by creating a new card
-T =>        add new dependent, by creating a new card
-edit =>     open the card in vim (must be installed)
-delete =>   delete the card
-exit =>     back to main menu
-help | ? => open this help message
-    "#
-}
+use std::{
+    collections::{BTreeSet, VecDeque},
+    ops::ControlFlow,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-#[derive(Clone)]
-enum CardAction {
-    NewDependency,
-    OldDependency,
-    NewDependent,
-    OldDependent,
-    Edit,
-    Delete,
-    /// Turn card into an instance of a new class
-    NewClass,
-    /// Turn card into an instance of an old class
-    OldClass,
-    NewAttribute,
-    OldAttribute,
-    FillAttribute,
-    SetBackRef,
-    /// Set the parent class of current class
-    ParentClass,
-
-    NewCard,
-    /// Turn card into statement
-    IntoStatement,
-    /// Turn a card into an attribute
-    IntoAttribute,
-    /// Turn card into a class
-    IntoClass,
-
-    IntoInstance,
-    IntoEvent,
+/// What happened to a card shown in review mode.
+#[derive(Clone, Copy, Debug)]
+pub enum ReviewOutcome {
+    Graded(Recall),
+    Skipped,
+    /// Go back to the previous card and remove its grade.
+    Undo,
 }
 
-#[derive(Clone)]
-enum ReviewAction {
-    Grade(Recall),
-    Help,
-    Skip,
-}
+fn choose_order(kind: ReviewKind) {
+    let orders = QueueOrder::all();
+    let names: Vec<&str> = orders.iter().map(QueueOrder::name).collect();
+    let order = orders[select_item(&names)];
 
-impl FromStr for CardAction {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.trim() {
-            "y" => Self::OldDependency,
-            "Y" => Self::NewDependency,
-            "t" => Self::OldDependent,
-            "T" => Self::NewDependent,
-            "c" => Self::OldClass,
-            "C" => Self::NewClass,
-            "p" => Self::ParentClass,
-            "a" => Self::OldAttribute,
-            "A" => Self::NewAttribute,
-            "fa" => Self::FillAttribute,
-            "ref" => Self::SetBackRef,
-            "n" => Self::NewCard,
-            "edit" => Self::Edit,
-            "delete" => Self::Delete,
-            "ic" => Self::IntoClass,
-            "ia" => Self::IntoAttribute,
-            "is" => Self::IntoStatement,
-            "ii" => Self::IntoInstance,
-            "ie" => Self::IntoEvent,
-            _ => return Err(()),
-        })
+    let mut config = Config::load();
+    match kind {
+        ReviewKind::Old => config.old_order = order,
+        ReviewKind::Pending => config.new_order = order,
     }
+    config.save();
 }
 
-impl FromStr for ReviewAction {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s.trim() {
-            "1" => Self::Grade(Recall::None),
-            "2" => Self::Grade(Recall::Late),
-            "3" => Self::Grade(Recall::Some),
-            "4" => Self::Grade(Recall::Perfect),
-            "help" | "?" => Self::Help,
-            "skip" | "s" => Self::Skip,
-            _ => return Err(()),
-        })
-    }
-}
+/// Suggested filter when cramming from the menu, cleared to cram every card.
+const CRAM_FILTER: &str = "finished == true & suspended == false";
 
 pub fn review_menu() {
-    let items = vec!["Old cards", "Pending cards", "exit"];
-
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .items(&items)
-        .default(0)
-        .interact()
-        .unwrap();
-
-    match selection {
-        0 => review_old(),
-        1 => review_new(),
-        2 => return,
-        _ => panic!(),
+    let mut opts = ReviewOptions::default();
+
+    loop {
+        let config = Config::load();
+        let filter = config.active_filter();
+        let filter_opt = format!("Filter: {}", config.active_filter);
+        let scope_opt = format!("Cards: {}", opts.scope.name());
+        let old_order_opt = format!("Order of old cards: {}", config.old_order.name());
+        let new_order_opt = format!("Order of pending cards: {}", config.new_order.name());
+        let items = vec![
+            "Old cards",
+            "Pending cards",
+            "Cram, without affecting scheduling",
+            filter_opt.as_str(),
+            scope_opt.as_str(),
+            old_order_opt.as_str(),
+            new_order_opt.as_str(),
+            "exit",
+        ];
+
+        let selection = Select::with_theme(&ColorfulTheme::default())
+            .items(&items)
+            .default(0)
+            .interact()
+            .unwrap();
+
+        match selection {
+            0 => return review_old(filter, &opts),
+            1 => return review_new(filter, &opts),
+            2 => return review_cram(edit_filter(CRAM_FILTER), &opts),
+            3 => filter_menu(),
+            4 => opts.scope = Scope::choose(),
+            5 => choose_order(ReviewKind::Old),
+            6 => choose_order(ReviewKind::Pending),
+            7 => return,
+            _ => panic!(),
+        }
     }
 }
 
-const DEFAULT_FILTER: &'static str =
-    "recall < 0.95 & finished == true & suspended == false & resolved == true & minrecrecall > 0.85 & minrecstab > 50 & lastreview > 0.5 & lapses < 2";
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ReviewKind {
+    #[default]
+    Old,
+    Pending,
+}
+
+/// Session settings that override the config.
+#[derive(Clone, Debug, Default)]
+pub struct ReviewOptions {
+    pub limit: Option<usize>,
+    pub minutes: Option<u32>,
+    pub order: Option<QueueOrder>,
+    /// Where to write the session summary as json.
+    pub summary_json: Option<PathBuf>,
+    /// Type the answer before it's revealed.
+    pub typed: bool,
+    /// Reveal, grade and pick actions with a single keypress.
+    pub single_key: bool,
+    /// Only review cards in these categories.
+    pub scope: Scope,
+    /// Skip the rest of the session for siblings of shown cards.
+    pub bury: bool,
+    /// Levels of weak dependencies to review right after a failed card.
+    pub drill_depth: Option<u32>,
+    /// Grades are only for feedback and don't affect scheduling, failed cards come back
+    /// until passed.
+    pub cram: bool,
+}
 
-pub fn review_new() {
-    let filter = DEFAULT_FILTER.to_string();
-    let mut cards = speki_core::Card::load_pending(Some(filter));
-    cards.shuffle(&mut thread_rng());
+pub fn review_new(filter: String, opts: &ReviewOptions) {
+    let cards = opts
+        .scope
+        .apply(speki_core::Card::load_pending(Some(filter)));
+    let order = opts.order.unwrap_or(Config::load().new_order);
 
-    review(cards);
+    review(order.apply(cards), ReviewKind::Pending, opts);
 }
 
-pub fn review_old() {
-    let filter = DEFAULT_FILTER.to_string();
-    let mut cards = speki_core::Card::load_non_pending(Some(filter));
-    cards.shuffle(&mut thread_rng());
+/// Crams the cards matching the filter, or every card if there's none.
+pub fn review_cram(filter: Option<String>, opts: &ReviewOptions) {
+    let cards = match filter {
+        Some(filter) => speki_core::cards_filtered(filter),
+        None => Card::load_all_cards()
+            .iter()
+            .map(|card| card.id())
+            .collect(),
+    };
+    let cards = opts.scope.apply(cards);
+    let order = opts.order.unwrap_or_default();
+    let opts = ReviewOptions {
+        cram: true,
+        ..opts.clone()
+    };
 
-    review(cards);
+    // the kind only matters for limits, which don't apply to cramming
+    review(order.apply(cards), ReviewKind::Old, &opts);
 }
 
-fn handle_review_action(card: CardId, action: ReviewAction) -> ControlFlow<()> {
-    let card = Card::from_id(card).unwrap();
+pub fn review_old(filter: String, opts: &ReviewOptions) {
+    let cards = opts
+        .scope
+        .apply(speki_core::Card::load_non_pending(Some(filter)));
+    let order = opts.order.unwrap_or(Config::load().old_order);
+
+    review(order.apply(cards), ReviewKind::Old, opts);
+}
+
+fn handle_review_action(
+    card: CardId,
+    action: ReviewAction,
+    shown: Instant,
+    reveal: Option<Duration>,
+    cram: bool,
+) -> ReviewOutcome {
     match action {
+        ReviewAction::Grade(grade) if cram => ReviewOutcome::Graded(grade),
         ReviewAction::Grade(grade) => {
-            speki_core::review(card.id(), grade);
-            ControlFlow::Break(())
-        }
-        ReviewAction::Skip => ControlFlow::Break(()),
-        ReviewAction::Help => {
-            notify(format!("{}", review_help()));
-            ControlFlow::Continue(())
+            speki_core::review(card, grade);
+            let time = ResponseTime {
+                timestamp: current_time(),
+                reveal,
+                grade: shown.elapsed(),
+            };
+            ResponseLog::push(card, time);
+            ReviewOutcome::Graded(grade)
         }
+        ReviewAction::Skip => ReviewOutcome::Skipped,
+        ReviewAction::Undo => ReviewOutcome::Undo,
     }
 }
 
@@ -433,11 +457,66 @@ fn handle_action(card: CardId, action: CardAction) -> ControlFlow<()> {
                 Card::from_id(card.id()).unwrap().set_ref(reff);
             }
         }
-        CardAction::Edit => speki_core::edit(card.id()),
+        CardAction::Edit => match Clozes::get(card.id()) {
+            Some(cloze) => {
+                let text: String = Input::with_theme(&ColorfulTheme::default())
+                    .with_prompt("cloze text")
+                    .with_initial_text(&cloze.text)
+                    .interact_text()
+                    .expect("Failed to read input");
+
+                if !is_cloze(&text) {
+                    notify("text has no cloze deletions, e.g. {{c1::answer}}");
+                } else {
+                    cloze::edit(card.id(), &text);
+                    if Card::from_id(card.id()).is_none() {
+                        return ControlFlow::Break(());
+                    }
+                }
+            }
+            None => {
+                speki_core::edit(card.id());
+                reverse::sync(card.id());
+            }
+        },
+        CardAction::Suspend => {
+            speki_core::set_suspended(card.id(), true);
+            notify("card suspended");
+            return ControlFlow::Break(());
+        }
+        CardAction::Unsuspend => {
+            speki_core::set_suspended(card.id(), false);
+            Snoozed::unsnooze(card.id());
+            notify("card unsuspended");
+        }
+        CardAction::Snooze => {
+            let Some(days) = get_input_opt("snooze for how many days?") else {
+                return ControlFlow::Continue(());
+            };
+            match days.trim().parse::<u32>() {
+                Ok(days) if days > 0 => {
+                    Snoozed::snooze(card.id(), days);
+                    return ControlFlow::Break(());
+                }
+                _ => notify("not a number of days"),
+            }
+        }
         CardAction::Delete => {
+            reverse::on_delete(card.id());
+            cloze::on_delete(card.id());
             speki_core::delete(card.id());
             return ControlFlow::Break(());
         }
+        CardAction::Reverse => {
+            if Reverses::reverse_of(card.id()).is_some() {
+                reverse::remove_reverse(card.id());
+                notify("reverse card removed");
+            } else if let Err(msg) = reverse::add_reverse(card.id()) {
+                notify(msg);
+            } else {
+                notify("reverse card added");
+            }
+        }
 
         CardAction::NewCard => {
             let _ = add_card(card.category());
@@ -447,58 +526,137 @@ fn handle_action(card: CardId, action: CardAction) -> ControlFlow<()> {
     ControlFlow::Continue(())
 }
 
-pub fn view_card(card: CardId, review_mode: bool) -> ControlFlow<()> {
+/// Shows a card and handles actions on it. In review mode, continues with what happened to
+/// the card. Breaks if the user wants to exit.
+pub fn view_card(card: CardId, review: Option<&ReviewOptions>) -> ControlFlow<(), ReviewOutcome> {
+    let review_mode = review.is_some();
+    let config = Config::load();
+    let typed = review.is_some_and(|opts| opts.typed || config.typed_answers);
+    let single_key = review.is_some_and(|opts| opts.single_key || config.single_key);
+    let reveal_with = match (typed, single_key) {
+        (true, _) => RevealWith::TypedAnswer,
+        (false, true) => RevealWith::Key,
+        (false, false) => RevealWith::Select,
+    };
     let mut show_backside = !review_mode;
+    let shown = Instant::now();
+    let mut reveal: Option<Duration> = None;
+    let slow_answer = config.slow_answer_secs.map(Duration::from_secs);
+    let mut suggested: Option<Recall> = None;
+    let keymap = Keymap::load();
 
     loop {
-        if print_card(card, show_backside).is_break() {
-            return ControlFlow::Continue(());
+        let mut slow = false;
+        let typed_answer = match print_card(card, show_backside, reveal_with) {
+            ControlFlow::Break(_) => return ControlFlow::Break(()),
+            ControlFlow::Continue(Revealed::Immediately) => None,
+            ControlFlow::Continue(Revealed::ByUser(answer)) => {
+                let elapsed = shown.elapsed();
+                reveal = Some(elapsed);
+                slow = slow_answer.is_some_and(|slow| elapsed > slow);
+                if slow {
+                    println!(
+                        "{}",
+                        style(format!(
+                            "took {:.0}s to reveal, consider grading 3 or lower",
+                            elapsed.as_secs_f32()
+                        ))
+                        .yellow()
+                    );
+                }
+                answer
+            }
+        };
+
+        if let Some(answer) = typed_answer {
+            let expected = Expected::from_card(&Card::from_id(card).unwrap()).unwrap();
+            let (typed, correct) = answer::diff(&answer, &expected);
+            let mut grade = answer::suggest_grade(&answer, &expected);
+            if slow && grade == Recall::Perfect {
+                grade = Recall::Some;
+            }
+            println!("your answer: {}", typed);
+            println!("correct:     {}", correct);
+            println!("suggested grade: {}", grade_number(grade));
+            println!();
+            suggested = Some(grade);
         }
 
         show_backside = true;
 
-        let txt: String = get_input("");
+        let txt: String = match suggested.take() {
+            Some(grade) => Input::new()
+                .with_initial_text(grade_number(grade))
+                .allow_empty(true)
+                .interact_text()
+                .expect("Failed to read input"),
+            None if single_key => match read_key() {
+                Some(Key::Char(':')) => get_input(":"),
+                Some(Key::Char(c)) => c.to_string(),
+                Some(Key::Escape) => return ControlFlow::Break(()),
+                Some(_) => continue,
+                None => get_input(""),
+            },
+            None => get_input(""),
+        };
 
-        if let Ok(action) = txt.parse::<ReviewAction>() {
-            if review_mode {
-                match handle_review_action(card, action) {
-                    ControlFlow::Continue(_) => continue,
-                    ControlFlow::Break(_) => return ControlFlow::Continue(()),
-                }
+        match keymap.parse(&txt) {
+            Some(Action::Review(action)) if review_mode => {
+                let cram = review.is_some_and(|opts| opts.cram);
+                let outcome = handle_review_action(card, action, shown, reveal, cram);
+                return ControlFlow::Continue(outcome);
             }
-        }
-
-        if let Ok(action) = txt.parse::<CardAction>() {
-            match handle_action(card, action) {
+            Some(Action::Card(action)) => match handle_action(card, action) {
                 ControlFlow::Continue(_) => continue,
-                ControlFlow::Break(_) => return ControlFlow::Continue(()),
-            }
-        } else {
-            if txt.contains("exit") {
-                return ControlFlow::Break(());
-            }
-
-            if txt.contains("find") {
+                ControlFlow::Break(_) => return ControlFlow::Continue(ReviewOutcome::Skipped),
+            },
+            Some(Action::Help) if single_key => notify(format!(
+                "{}\npress ':' to type commands longer than one key, like 'edit'",
+                keymap.help(review_mode)
+            )),
+            Some(Action::Help) => notify(keymap.help(review_mode)),
+            Some(Action::Exit) => return ControlFlow::Break(()),
+            Some(Action::Find) => {
                 if let Some(card) = select_from_all_cards() {
-                    view_card(card, false);
+                    view_card(card, None);
                 }
+            }
+            Some(Action::Review(_)) | None => {
+                clear_terminal();
 
-                continue;
+                Select::with_theme(&ColorfulTheme::default())
+                    .with_prompt("write 'help' to see list of possible action")
+                    .items(&["back to card"])
+                    .default(0)
+                    .interact()
+                    .expect("Failed to make selection");
             }
+        }
+    }
+}
 
-            clear_terminal();
+#[derive(Clone, Copy, PartialEq)]
+enum RevealWith {
+    Select,
+    /// Type the answer, which is then compared to the back side.
+    TypedAnswer,
+    /// Single keypress.
+    Key,
+}
 
-            Select::with_theme(&ColorfulTheme::default())
-                .with_prompt("write 'help' to see list of possible action")
-                .items(&["back to card"])
-                .default(0)
-                .interact()
-                .expect("Failed to make selection");
-        };
-    }
+/// How the back side of a printed card came to be shown.
+enum Revealed {
+    /// Right away, without a reveal step.
+    Immediately,
+    /// By the user, with the answer they typed first if any.
+    ByUser(Option<String>),
 }
 
-fn print_card(card: CardId, mut show_backside: bool) -> ControlFlow<()> {
+fn print_card(
+    card: CardId,
+    mut show_backside: bool,
+    reveal_with: RevealWith,
+) -> ControlFlow<(), Revealed> {
     clear_terminal();
     let card = speki_core::card_from_id(card);
 
@@ -517,11 +675,14 @@ fn print_card(card: CardId, mut show_backside: bool) -> ControlFlow<()> {
             }
         }
 
-        AnyType::Normal(normal) => {
-            let front = card.print();
-            let back = normal.back.to_string();
-            (front, back)
-        }
+        AnyType::Normal(normal) => match Clozes::get(card.id()) {
+            Some(cloze) => (cloze.front(), cloze.back()),
+            None => {
+                let front = card.print();
+                let back = normal.back.to_string();
+                (front, back)
+            }
+        },
         AnyType::Unfinished(_) => {
             show_backside = true;
             let front = card.print();
@@ -555,54 +716,279 @@ fn print_card(card: CardId, mut show_backside: bool) -> ControlFlow<()> {
         card.maturity(),
         card.card_type().type_name()
     );
+    if leech::is_leech(&card) {
+        println!(
+            "{}",
+            style(format!("leech ({} lapses)", card.lapses())).red()
+        );
+    }
     println!();
     println!("{}", &front);
+    let mut revealed = Revealed::Immediately;
+
     if !show_backside {
         println!();
-        match Select::with_theme(&ColorfulTheme::default())
+
+        let mut typed_answer = None;
+        if reveal_with == RevealWith::TypedAnswer && Expected::from_card(&card).is_some() {
+            let answer: String = Input::new()
+                .with_prompt("answer")
+                .allow_empty(true)
+                .interact_text()
+                .expect("Failed to read input");
+            typed_answer = Some(answer);
+        } else if reveal_with == RevealWith::Key {
+            println!("{}", style("press space to reveal, esc to exit").dim());
+            loop {
+                match read_key() {
+                    Some(Key::Char(' ') | Key::Enter) | None => break,
+                    Some(Key::Escape) => return ControlFlow::Break(()),
+                    Some(_) => {}
+                }
+            }
+        } else if Select::with_theme(&ColorfulTheme::default())
             .with_prompt("")
             .items(&opts)
             .default(0)
             .interact()
             .expect("Failed to make selection")
+            != 0
         {
-            0 => {
-                clear_terminal();
-                println!(
-                    "recall: {:.1}%, stability: {:.2} days, card_type: {}",
-                    (card.recall_rate().unwrap_or_default() * 100.),
-                    card.maturity(),
-                    card.card_type().type_name()
-                );
-                println!();
-                println!("{}", &front);
-                println!();
-                println!("-------------------------------------------------");
-                println!();
-            }
-            _ => return ControlFlow::Break(()),
+            return ControlFlow::Break(());
         }
+        revealed = Revealed::ByUser(typed_answer);
+
+        clear_terminal();
+        println!(
+            "recall: {:.1}%, stability: {:.2} days, card_type: {}",
+            (card.recall_rate().unwrap_or_default() * 100.),
+            card.maturity(),
+            card.card_type().type_name()
+        );
+        println!();
+        println!("{}", &front);
+        println!();
+        println!("-------------------------------------------------");
+        println!();
     }
 
     println!("{}", &back);
     println!();
     print_card_info(card.id());
-    ControlFlow::Continue(())
+    ControlFlow::Continue(revealed)
+}
+
+/// A card graded during a session, and what it changed so undo can revert it.
+struct Graded {
+    card: CardId,
+    buried: BTreeSet<CardId>,
+    /// Dependencies queued by drilling down after it failed, `None` if it didn't.
+    drilled: Option<Vec<CardId>>,
+    /// Suspended from the leech prompt after this grade.
+    suspended: bool,
 }
 
-pub fn review(cards: Vec<CardId>) {
+pub fn review(mut cards: Vec<CardId>, kind: ReviewKind, opts: &ReviewOptions) {
+    let limits = Config::load().limits;
+    let snoozed = Snoozed::load();
+    cards.retain(|card| !snoozed.contains(*card));
+    let available = cards.len();
+
+    let remaining = if opts.cram {
+        opts.limit
+    } else {
+        limits.remaining(kind, opts.limit)
+    };
+    if let Some(remaining) = remaining {
+        cards.truncate(remaining);
+    }
+
     if cards.is_empty() {
         clear_terminal();
-        notify("nothing to review!");
+        if available > 0 {
+            notify("review limit reached for today!");
+        } else {
+            notify("nothing to review!");
+        }
         return;
     } else {
         clear_terminal();
-        notify(format!("reviewing {} cards", cards.len()));
+        notify(format!("reviewing {} of {} cards", cards.len(), available));
+    }
+
+    let bury = opts.bury || Config::load().bury_siblings;
+    let mut buried = 0;
+    let drill_depth = opts.drill_depth.or(Config::load().drill_depth);
+    // failed cards already queued again for the end of the session
+    let mut drilled: BTreeSet<CardId> = BTreeSet::new();
+    let budget = limits.time_budget(opts.minutes);
+    let start = Instant::now();
+    let mut queue: VecDeque<CardId> = cards.into();
+    // cards graded this session, most recent last
+    let mut graded: Vec<Graded> = vec![];
+    let mut summary = SessionSummary::default();
+
+    while let Some(card) = queue.pop_front() {
+        if budget.is_some_and(|budget| start.elapsed() >= budget) {
+            clear_terminal();
+            notify("time budget used up!");
+            break;
+        }
+
+        // deleted earlier in the session, e.g. as the reverse of a deleted card
+        if Card::from_id(card).is_none() {
+            continue;
+        }
+
+        let before = CardSnapshot::take(card);
+        let shown = Instant::now();
+
+        match view_card(card, Some(opts)) {
+            ControlFlow::Break(_) => break,
+            ControlFlow::Continue(ReviewOutcome::Graded(grade)) => {
+                let lapses_before = before.lapses();
+                if !opts.cram {
+                    DailyCount::increment(kind);
+                }
+                let siblings = if bury {
+                    bury_siblings(card, &mut queue)
+                } else {
+                    BTreeSet::new()
+                };
+                buried += siblings.len();
+                graded.push(Graded {
+                    card,
+                    buried: siblings,
+                    drilled: None,
+                    suspended: false,
+                });
+                summary.record(card, grade, shown.elapsed(), before);
+
+                let lapsed = Card::from_id(card)
+                    .is_some_and(|card| card.lapses() > lapses_before && leech::is_leech(&card));
+                if lapsed && handle_leech(card) {
+                    graded.last_mut().unwrap().suspended = true;
+                }
+
+                let failed = matches!(grade, Recall::None | Recall::Late);
+                if opts.cram && failed {
+                    queue.push_back(card);
+                } else if let Some(depth) = drill_depth.filter(|_| failed) {
+                    if drilled.insert(card) {
+                        let queued = drill_down(card, depth, &graded, &mut queue);
+                        graded.last_mut().unwrap().drilled = Some(queued);
+                    }
+                }
+            }
+            ControlFlow::Continue(ReviewOutcome::Skipped) => {
+                if bury {
+                    buried += bury_siblings(card, &mut queue).len();
+                }
+            }
+            ControlFlow::Continue(ReviewOutcome::Undo) => {
+                queue.push_front(card);
+                match graded.pop() {
+                    Some(prev) => {
+                        if let Some(dependencies) = prev.drilled {
+                            drilled.remove(&prev.card);
+                            queue.retain(|card| !dependencies.contains(card));
+                        }
+                        // it might have been queued again after failing
+                        queue.retain(|card| *card != prev.card);
+                        buried -= prev.buried.len();
+                        queue.extend(prev.buried);
+                        if prev.suspended {
+                            speki_core::set_suspended(prev.card, false);
+                        }
+                        if !opts.cram {
+                            undo_review(prev.card);
+                            DailyCount::decrement(kind);
+                        }
+                        summary.remove_last(prev.card);
+                        queue.push_front(prev.card);
+                    }
+                    None => notify("nothing to undo"),
+                }
+            }
+        }
+    }
+
+    if buried > 0 {
+        notify(format!(
+            "buried {} sibling cards until next session",
+            buried
+        ));
     }
 
-    for card in cards {
-        if view_card(card, true).is_break() {
-            return;
+    if summary.is_empty() {
+        return;
+    }
+
+    if let Some(path) = &opts.summary_json {
+        if let Err(e) = summary.write_json(path) {
+            notify(format!("failed to write summary: {}", e));
+        }
+    }
+
+    summary.print();
+}
+
+/// Reviews the weak dependencies of a failed card next, and the card itself again at the end.
+/// Returns the dependencies that weren't queued already.
+fn drill_down(
+    card: CardId,
+    depth: u32,
+    graded: &[Graded],
+    queue: &mut VecDeque<CardId>,
+) -> Vec<CardId> {
+    let exclude: BTreeSet<CardId> = graded.iter().map(|graded| graded.card).collect();
+    let dependencies = weak_dependencies(card, depth, &exclude);
+    let added = dependencies
+        .iter()
+        .filter(|dependency| !queue.contains(dependency))
+        .copied()
+        .collect();
+
+    queue.retain(|card| !dependencies.contains(card));
+    for dependency in dependencies.into_iter().rev() {
+        queue.push_front(dependency);
+    }
+    queue.push_back(card);
+    added
+}
+
+/// Removes the siblings of a shown card from the rest of the session, and returns them.
+fn bury_siblings(card: CardId, queue: &mut VecDeque<CardId>) -> BTreeSet<CardId> {
+    let queued: Vec<CardId> = queue.iter().copied().collect();
+    let siblings = siblings_among(card, &queued);
+    queue.retain(|card| !siblings.contains(card));
+    siblings
+}
+
+/// Returns whether the card was suspended.
+fn handle_leech(card: CardId) -> bool {
+    match leech::ask(card) {
+        LeechAction::Suspend => {
+            speki_core::set_suspended(card, true);
+            notify("card suspended");
+            return true;
+        }
+        LeechAction::Rewrite => {
+            let _ = handle_action(card, CardAction::Edit);
         }
+        LeechAction::NewDependency => {
+            let _ = handle_action(card, CardAction::NewDependency);
+        }
+        LeechAction::Keep => {}
     }
+
+    false
+}
+
+/// Removes the most recent review of the card.
+fn undo_review(card: CardId) {
+    let mut reviews = Reviews::load(card);
+    reviews.0.pop();
+    reviews.save(card);
+    ResponseLog::pop(card);
 }
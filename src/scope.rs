@@ -0,0 +1,100 @@
+use crate::utils::category_from_path;
+use dialoguer::{theme::ColorfulTheme, MultiSelect};
+use speki_core::{categories::Category, collections::Collection, common::CardId, Card};
+use std::path::PathBuf;
+
+/// Categories a review session is limited to, including their subcategories.
+/// Empty means every card.
+#[derive(Clone, Debug, Default)]
+pub struct Scope(Vec<PathBuf>);
+
+/// Collections are the top level categories of the cards folder.
+fn collection_category(name: &str) -> Result<Category, String> {
+    Collection::load_all()
+        .iter()
+        .find(|col| col.name() == name)
+        .map(|col| Category::default().join(col.name()))
+        .ok_or_else(|| format!("no collection named: {}", name))
+}
+
+impl Scope {
+    pub fn from_args(categories: &[String], collections: &[String]) -> Result<Self, String> {
+        let mut paths: Vec<PathBuf> = categories
+            .iter()
+            .map(|path| category_from_path(path).as_path())
+            .collect();
+
+        for name in collections {
+            paths.push(collection_category(name)?.as_path());
+        }
+
+        Ok(Self(paths))
+    }
+
+    /// Lets the user pick categories and collections. Picking none means every card.
+    pub fn choose() -> Self {
+        let mut categories: Vec<Category> = Collection::load_all()
+            .iter()
+            .map(|col| Category::default().join(col.name()))
+            .collect();
+        let mut items: Vec<String> = Collection::load_all()
+            .iter()
+            .map(|col| format!("collection: {}", col.name()))
+            .collect();
+
+        for category in Category::load_all(None) {
+            items.push(format!("{}", category.print_it_with_depth()));
+            categories.push(category);
+        }
+
+        let chosen = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("space to pick, enter to confirm")
+            .items(&items)
+            .interact()
+            .unwrap();
+
+        Self(
+            chosen
+                .into_iter()
+                .map(|idx| categories[idx].as_path())
+                .collect(),
+        )
+    }
+
+    pub fn contains(&self, card: CardId) -> bool {
+        if self.0.is_empty() {
+            return true;
+        }
+
+        let Some(card) = Card::from_id(card) else {
+            return false;
+        };
+        let path = card.category().as_path();
+        self.0.iter().any(|scope| path.starts_with(scope))
+    }
+
+    pub fn apply(&self, cards: Vec<CardId>) -> Vec<CardId> {
+        cards
+            .into_iter()
+            .filter(|card| self.contains(*card))
+            .collect()
+    }
+
+    pub fn name(&self) -> String {
+        if self.0.is_empty() {
+            return "all cards".to_string();
+        }
+
+        let root = Category::default().as_path();
+        let names: Vec<String> = self
+            .0
+            .iter()
+            .map(|path| match path.strip_prefix(&root) {
+                Ok(path) if path.as_os_str().is_empty() => "/".to_string(),
+                Ok(path) => path.to_string_lossy().to_string(),
+                Err(_) => path.to_string_lossy().to_string(),
+            })
+            .collect();
+        names.join(", ")
+    }
+}
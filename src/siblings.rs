@@ -0,0 +1,48 @@
+use crate::cloze::Clozes;
+use speki_core::{card::AnyType, common::CardId, Card};
+use std::collections::BTreeSet;
+
+/// Cards in the same group give each other away when reviewed close together.
+#[derive(PartialEq)]
+enum Group {
+    /// Attributes of the same instance.
+    Instance(CardId),
+    /// Instances of the same class.
+    Class(CardId),
+}
+
+fn group(card: &Card<AnyType>) -> Option<Group> {
+    match card.card_type() {
+        AnyType::Attribute(attribute) => Some(Group::Instance(attribute.instance)),
+        AnyType::Instance(instance) => Some(Group::Class(instance.class)),
+        _ => None,
+    }
+}
+
+/// Direct dependencies, dependents and cloze siblings.
+fn linked(card: &Card<AnyType>) -> BTreeSet<CardId> {
+    let mut linked: BTreeSet<CardId> = card.dependency_ids().iter().copied().collect();
+    linked.extend(speki_core::get_cached_dependents(card.id()));
+    linked.extend(Clozes::siblings(card.id()));
+    linked
+}
+
+/// Cards among `cards` that are siblings of `card`.
+pub fn siblings_among(card: CardId, cards: &[CardId]) -> BTreeSet<CardId> {
+    let Some(card) = Card::from_id(card) else {
+        return BTreeSet::new();
+    };
+    let card_group = group(&card);
+    let linked = linked(&card);
+
+    cards
+        .iter()
+        .copied()
+        .filter(|other| *other != card.id())
+        .filter(|other| {
+            linked.contains(other)
+                || (card_group.is_some()
+                    && Card::from_id(*other).is_some_and(|other| group(&other) == card_group))
+        })
+        .collect()
+}
@@ -0,0 +1,98 @@
+use crate::{
+    list::{csv_escape, ListFormat},
+    utils::{format_timestamp, load_json, save_json, DAY},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use speki_core::{
+    common::{current_time, CardId},
+    paths::get_share_path,
+    Card,
+};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+/// Cards kept out of review until a unix timestamp, by card.
+#[derive(Serialize, Deserialize, Default)]
+pub struct Snoozed(BTreeMap<CardId, Duration>);
+
+impl Snoozed {
+    fn path() -> PathBuf {
+        get_share_path().join("snoozed")
+    }
+
+    /// Loads the snoozed cards, forgetting the ones whose date has passed.
+    pub fn load() -> Self {
+        let mut snoozed: Self = load_json(&Self::path()).unwrap_or_default();
+
+        let now = current_time();
+        snoozed.0.retain(|_, until| *until > now);
+        snoozed
+    }
+
+    fn save(&self) {
+        save_json(&Self::path(), self);
+    }
+
+    pub fn contains(&self, card: CardId) -> bool {
+        self.0.contains_key(&card)
+    }
+
+    pub fn until(&self, card: CardId) -> Option<Duration> {
+        self.0.get(&card).copied()
+    }
+
+    pub fn snooze(card: CardId, days: u32) {
+        let mut snoozed = Self::load();
+        snoozed.0.insert(card, current_time() + DAY * days);
+        snoozed.save();
+    }
+
+    pub fn unsnooze(card: CardId) {
+        let mut snoozed = Self::load();
+        if snoozed.0.remove(&card).is_some() {
+            snoozed.save();
+        }
+    }
+}
+
+/// Prints suspended cards, and snoozed cards with the date they come back.
+pub fn list_held(format: ListFormat) {
+    let mut rows: Vec<(CardId, String)> = speki_core::cards_filtered("suspended == true".into())
+        .into_iter()
+        .map(|card| (card, "suspended".to_string()))
+        .collect();
+
+    for (card, until) in Snoozed::load().0 {
+        rows.push((card, format!("snoozed until {}", format_timestamp(until))));
+    }
+
+    let front = |card: CardId| {
+        Card::from_id(card)
+            .map(|card| card.print())
+            .unwrap_or_default()
+    };
+
+    match format {
+        ListFormat::Jsonl => {
+            for (card, status) in rows {
+                let obj = json!({
+                    "id": card.0.to_string(),
+                    "front": front(card),
+                    "status": status,
+                });
+                println!("{}", obj);
+            }
+        }
+        ListFormat::Csv => {
+            println!("id,front,status");
+            for (card, status) in rows {
+                println!("{},{},{}", card.0, csv_escape(&front(card)), status);
+            }
+        }
+        ListFormat::Table => {
+            for (card, status) in rows {
+                println!("{:<28} {}  {}", status, card.0, front(card));
+            }
+        }
+    }
+}
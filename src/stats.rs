@@ -0,0 +1,143 @@
+use crate::{
+    timing::ResponseLog,
+    utils::{find_card, format_timestamp, DAY},
+};
+use console::style;
+use speki_core::{card::AnyType, common::current_time, reviews::Reviews, Card};
+use std::time::Duration;
+
+/// Recall expected `elapsed` days after the last review, where stability is the
+/// amount of days until recall drops to 90%.
+pub fn projected_recall(stability: f32, elapsed: f32) -> f32 {
+    if stability <= 0. {
+        return 0.;
+    }
+
+    0.9_f32.powf(elapsed.max(0.) / stability)
+}
+
+/// Days after the last review until recall drops to `target`.
+pub fn days_until_recall(stability: f32, target: f32) -> f32 {
+    stability * target.ln() / 0.9_f32.ln()
+}
+
+/// Stability that makes the decay curve go through the recall speki-core gives the card
+/// now, so projections agree with `Card::recall_rate`. Falls back to the stability of the
+/// card when there's nothing to go through.
+pub fn anchored_stability(card: &Card<AnyType>, elapsed: f32) -> f32 {
+    match card.recall_rate() {
+        Some(recall) if recall > 0. && recall < 1. && elapsed > 0. => {
+            elapsed * 0.9_f32.ln() / recall.ln()
+        }
+        _ => card.maturity(),
+    }
+}
+
+/// When recall drops to `target`, or `None` if it never does.
+pub fn due_time(last_review: Duration, stability: f32, target: f32) -> Option<Duration> {
+    let days = days_until_recall(stability, target);
+    if !days.is_finite() {
+        return None;
+    }
+
+    let wait = Duration::try_from_secs_f32(days.max(0.) * DAY.as_secs_f32()).ok()?;
+    last_review.checked_add(wait)
+}
+
+fn recall_curve(stability: f32, elapsed: f32) -> Vec<String> {
+    let width = 50;
+    let height = 10;
+    let max_days = (stability * 2.).max(elapsed * 1.2).max(1.);
+
+    let mut rows = vec![vec![' '; width]; height];
+    for (x, col) in (0..width).map(|x| (x, x as f32 / (width - 1) as f32 * max_days)) {
+        let recall = projected_recall(stability, col);
+        let y = ((1. - recall) * (height - 1) as f32).round() as usize;
+        rows[y.min(height - 1)][x] = '*';
+    }
+
+    let now = ((elapsed / max_days) * (width - 1) as f32).round() as usize;
+    for row in rows.iter_mut() {
+        if row[now.min(width - 1)] == ' ' {
+            row[now.min(width - 1)] = '|';
+        }
+    }
+
+    let mut lines: Vec<String> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(y, row)| {
+            let recall = 100. - (y as f32 / (height - 1) as f32) * 100.;
+            format!("{:>4.0}% {}", recall, row.into_iter().collect::<String>())
+        })
+        .collect();
+
+    lines.push(format!(
+        "      0 days{:>width$}",
+        format!("{:.0} days", max_days),
+        width = width - 6
+    ));
+    lines.push("      | = now".to_string());
+    lines
+}
+
+pub fn stats(query: &str) -> Result<(), String> {
+    let id = find_card(query)?;
+    let card = Card::from_id(id).unwrap();
+    let reviews = Reviews::load(id);
+
+    println!("{}", style(card.print()).bold());
+    println!("id: {}", id.0);
+    println!("card_type: {}", card.card_type().type_name());
+    println!();
+
+    let stability = card.maturity();
+    match card.recall_rate() {
+        Some(recall) => println!("recall: {:.1}%", recall * 100.),
+        None => println!("recall: not reviewed yet"),
+    }
+    println!("stability: {:.2} days", stability);
+    println!("lapses: {}", card.lapses());
+
+    let Some(last) = reviews.0.last() else {
+        return Ok(());
+    };
+
+    let since = current_time().saturating_sub(last.timestamp);
+    let elapsed = since.as_secs_f32() / DAY.as_secs_f32();
+    let stability = anchored_stability(&card, elapsed);
+    let due = due_time(last.timestamp, stability, 0.9)
+        .map(format_timestamp)
+        .unwrap_or_else(|| "never".to_string());
+    println!("next due (90% recall): {}", due);
+    println!();
+
+    println!("{}", style("reviews").bold());
+    let times = ResponseLog::load(id);
+    for review in &reviews.0 {
+        let time = match times.find(review.timestamp) {
+            Some(time) => format!(
+                "  reveal: {}, grade: {:.1}s",
+                time.reveal
+                    .map(|reveal| format!("{:.1}s", reveal.as_secs_f32()))
+                    .unwrap_or_else(|| "-".to_string()),
+                time.grade.as_secs_f32()
+            ),
+            None => String::new(),
+        };
+
+        println!(
+            "{}  {:<8}{}",
+            format_timestamp(review.timestamp),
+            format!("{:?}", review.grade),
+            time
+        );
+    }
+    println!();
+
+    for line in recall_curve(stability, elapsed) {
+        println!("{}", line);
+    }
+
+    Ok(())
+}
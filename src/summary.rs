@@ -0,0 +1,195 @@
+use crate::utils::notify;
+use console::style;
+use serde_json::json;
+use speki_core::{common::CardId, reviews::Recall, Card};
+use std::{fs, path::Path, time::Duration};
+
+struct Entry {
+    card: CardId,
+    grade: Recall,
+    time: Duration,
+    recall_before: Option<f32>,
+    stability_before: f32,
+    stability_after: f32,
+    lapses_before: u32,
+    lapses_after: u32,
+}
+
+/// Grades given during a review session.
+#[derive(Default)]
+pub struct SessionSummary {
+    entries: Vec<Entry>,
+}
+
+/// Recall, stability and lapses of a card right before it's graded.
+pub struct CardSnapshot {
+    recall: Option<f32>,
+    stability: f32,
+    lapses: u32,
+}
+
+impl CardSnapshot {
+    pub fn take(card: CardId) -> Self {
+        let card = Card::from_id(card).unwrap();
+        Self {
+            recall: card.recall_rate(),
+            stability: card.maturity(),
+            lapses: card.lapses(),
+        }
+    }
+
+    pub fn lapses(&self) -> u32 {
+        self.lapses
+    }
+}
+
+const GRADES: [Recall; 4] = [Recall::None, Recall::Late, Recall::Some, Recall::Perfect];
+
+impl SessionSummary {
+    pub fn record(&mut self, card: CardId, grade: Recall, time: Duration, before: CardSnapshot) {
+        let after = CardSnapshot::take(card);
+        self.entries.push(Entry {
+            card,
+            grade,
+            time,
+            recall_before: before.recall,
+            stability_before: before.stability,
+            stability_after: after.stability,
+            lapses_before: before.lapses,
+            lapses_after: after.lapses,
+        });
+    }
+
+    /// Forgets the last grade of the card, when it's undone.
+    pub fn remove_last(&mut self, card: CardId) {
+        if let Some(idx) = self.entries.iter().rposition(|entry| entry.card == card) {
+            self.entries.remove(idx);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn grade_count(&self, grade: Recall) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| entry.grade == grade)
+            .count()
+    }
+
+    fn avg_time(&self) -> Duration {
+        if self.entries.is_empty() {
+            return Duration::default();
+        }
+
+        let total: Duration = self.entries.iter().map(|entry| entry.time).sum();
+        total / self.entries.len() as u32
+    }
+
+    fn lapsed(&self) -> Vec<CardId> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.lapses_after > entry.lapses_before)
+            .map(|entry| entry.card)
+            .collect()
+    }
+
+    /// Cards sorted by how many days of stability their grade cost, biggest drop first.
+    ///
+    /// Recall right after a review is always close to 100%, so the drop in recall is
+    /// measured by the stability that decides how fast it decays from there.
+    fn stability_drops(&self, qty: usize) -> Vec<(CardId, f32)> {
+        let mut drops: Vec<(CardId, f32)> = self
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let drop = entry.stability_before - entry.stability_after;
+                (drop > 0.).then_some((entry.card, drop))
+            })
+            .collect();
+
+        drops.sort_by(|a, b| b.1.total_cmp(&a.1));
+        drops.truncate(qty);
+        drops
+    }
+
+    pub fn print(&self) {
+        let name = |id: &CardId| {
+            Card::from_id(*id)
+                .map(|card| card.print())
+                .unwrap_or_default()
+        };
+        let mut out = String::new();
+
+        out.push_str(&format!("{}\n\n", style("session summary").bold()));
+        out.push_str(&format!("cards reviewed: {}\n", self.entries.len()));
+        out.push_str(&format!(
+            "average time per card: {:.1}s\n\n",
+            self.avg_time().as_secs_f32()
+        ));
+
+        for grade in GRADES {
+            let qty = self.grade_count(grade);
+            let percentage = qty as f32 / self.entries.len().max(1) as f32 * 100.;
+            out.push_str(&format!(
+                "{:<8} {:>4} {:>5.1}% {}\n",
+                format!("{:?}", grade),
+                qty,
+                percentage,
+                "#".repeat((percentage / 2.) as usize)
+            ));
+        }
+
+        let lapsed = self.lapsed();
+        if !lapsed.is_empty() {
+            out.push_str(&format!("\n{}\n", style("newly lapsed").bold()));
+            for id in &lapsed {
+                out.push_str(&format!("{}\n", name(id)));
+            }
+        }
+
+        let drops = self.stability_drops(5);
+        if !drops.is_empty() {
+            out.push_str(&format!("\n{}\n", style("biggest stability drops").bold()));
+            for (id, drop) in &drops {
+                out.push_str(&format!("-{:.1} days  {}\n", drop, name(id)));
+            }
+        }
+
+        notify(out);
+    }
+
+    pub fn write_json(&self, path: &Path) -> std::io::Result<()> {
+        let grades: serde_json::Map<String, serde_json::Value> = GRADES
+            .iter()
+            .map(|grade| (format!("{:?}", grade), self.grade_count(*grade).into()))
+            .collect();
+
+        let cards: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                json!({
+                    "id": entry.card.0.to_string(),
+                    "grade": format!("{:?}", entry.grade),
+                    "seconds": entry.time.as_secs_f32(),
+                    "recall_before": entry.recall_before,
+                    "stability_before": entry.stability_before,
+                    "stability_after": entry.stability_after,
+                    "lapsed": entry.lapses_after > entry.lapses_before,
+                })
+            })
+            .collect();
+
+        let summary = json!({
+            "reviewed": self.entries.len(),
+            "grades": grades,
+            "avg_seconds": self.avg_time().as_secs_f32(),
+            "lapsed": self.lapsed().iter().map(|id| id.0.to_string()).collect::<Vec<_>>(),
+            "cards": cards,
+        });
+
+        fs::write(path, serde_json::to_string_pretty(&summary).unwrap())
+    }
+}
@@ -0,0 +1,69 @@
+use crate::utils::{load_json, save_json};
+use serde::{Deserialize, Serialize};
+use speki_core::{common::CardId, paths::get_share_path};
+use std::{fs, path::PathBuf, time::Duration};
+
+/// How long it took to answer a card during a review.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct ResponseTime {
+    /// When the review was recorded, to match it with the review log.
+    pub timestamp: Duration,
+    /// From showing the card until the answer was revealed. `None` if the back side was
+    /// shown right away.
+    pub reveal: Option<Duration>,
+    /// From showing the card until it was graded.
+    pub grade: Duration,
+}
+
+/// Response times of a card, stored next to the reviews in the share folder.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct ResponseLog(pub Vec<ResponseTime>);
+
+impl ResponseLog {
+    pub fn dir() -> PathBuf {
+        let path = get_share_path().join("timings");
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn path(card: CardId) -> PathBuf {
+        Self::dir().join(card.0.to_string())
+    }
+
+    pub fn load(card: CardId) -> Self {
+        load_json(&Self::path(card)).unwrap_or_default()
+    }
+
+    fn save(&self, card: CardId) {
+        save_json(&Self::path(card), self);
+    }
+
+    pub fn push(card: CardId, time: ResponseTime) {
+        let mut log = Self::load(card);
+        log.0.push(time);
+        log.save(card);
+    }
+
+    pub fn pop(card: CardId) {
+        let mut log = Self::load(card);
+        log.0.pop();
+        log.save(card);
+    }
+
+    /// The response time recorded together with a review at the given time.
+    pub fn find(&self, review_time: Duration) -> Option<&ResponseTime> {
+        let tolerance = Duration::from_secs(60);
+        let diff = |time: &ResponseTime| {
+            if time.timestamp > review_time {
+                time.timestamp - review_time
+            } else {
+                review_time - time.timestamp
+            }
+        };
+
+        self.0
+            .iter()
+            .filter(|time| diff(time) < tolerance)
+            .min_by_key(|time| diff(time))
+    }
+}
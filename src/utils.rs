@@ -1,4 +1,6 @@
+use chrono::{DateTime, Local, NaiveDate};
 use dialoguer::{theme::ColorfulTheme, Input, Select};
+use serde::{de::DeserializeOwned, Serialize};
 use speki_core::{
     attribute::{Attribute, AttributeId},
     card::AnyType,
@@ -6,6 +8,36 @@ use speki_core::{
     common::CardId,
     Card,
 };
+use std::{
+    fs::{self, read_to_string},
+    io::Write,
+    path::Path,
+    time::Duration,
+};
+
+pub const DAY: Duration = Duration::from_secs(86400);
+
+/// Reads a json file, `None` if it's missing or doesn't parse.
+pub fn load_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// Reads a json file, the default if it's missing and `Err` if it doesn't parse, so the
+/// caller can avoid saving over it.
+pub fn try_load_json<T: DeserializeOwned + Default>(path: &Path) -> serde_json::Result<T> {
+    match read_to_string(path) {
+        Ok(s) => serde_json::from_str(&s),
+        Err(_) => Ok(T::default()),
+    }
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T) {
+    let s: String = serde_json::to_string_pretty(value).unwrap();
+    let mut file = fs::File::create(path).unwrap();
+    file.write_all(s.as_bytes()).unwrap();
+}
 
 #[allow(dead_code)]
 pub fn notify(msg: impl Into<String>) {
@@ -83,6 +115,75 @@ pub fn select_from_all_cards() -> Option<CardId> {
     .into()
 }
 
+pub fn parse_card_id(s: &str) -> Option<CardId> {
+    let id: uuid::Uuid = s.trim().parse().ok()?;
+    Some(CardId(id))
+}
+
+/// Finds a card either by its id or by its front text.
+///
+/// Exact matches (ignoring case) win over partial ones, and a query matching several
+/// cards equally well is an error.
+pub fn find_card(query: &str) -> Result<CardId, String> {
+    if let Some(id) = parse_card_id(query) {
+        return match Card::from_id(id) {
+            Some(_) => Ok(id),
+            None => Err(format!("no card with id: {}", query)),
+        };
+    }
+
+    let query = query.trim().to_lowercase();
+    let mut exact = vec![];
+    let mut partial = vec![];
+
+    for card in Card::load_all_cards() {
+        let front = card.print().to_lowercase();
+        if front == query {
+            exact.push(card);
+        } else if front.contains(&query) {
+            partial.push(card);
+        }
+    }
+
+    let matches = if exact.is_empty() { partial } else { exact };
+
+    match matches.as_slice() {
+        [] => Err(format!("no card matching: {}", query)),
+        [card] => Ok(card.id()),
+        cards => {
+            let mut msg = format!("{} cards match '{}':", cards.len(), query);
+            for card in cards.iter().take(10) {
+                msg.push_str(&format!("\n  {}  {}", card.id().0, card.print()));
+            }
+            Err(msg)
+        }
+    }
+}
+
+/// Category from a slash separated path like "physics/mechanics".
+pub fn category_from_path(path: &str) -> Category {
+    path.split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .fold(Category::default(), |cat, dir| cat.join(dir))
+}
+
+fn local_time(time: Duration) -> Option<DateTime<Local>> {
+    DateTime::from_timestamp(time.as_secs() as i64, 0).map(|time| time.with_timezone(&Local))
+}
+
+/// The calendar day of a unix timestamp in local time.
+pub fn local_date(time: Duration) -> Option<NaiveDate> {
+    local_time(time).map(|time| time.date_naive())
+}
+
+/// Formats a unix timestamp as "YYYY-MM-DD HH:MM" in local time.
+pub fn format_timestamp(time: Duration) -> String {
+    local_time(time)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
 pub fn clear_terminal() {
     use std::io::Write;
     print!("\x1B[2J\x1B[H");
@@ -131,6 +232,11 @@ pub fn get_input_opt(prompt: &str) -> Option<String> {
     }
 }
 
+/// Reads a single keypress, `None` if the terminal doesn't support it.
+pub fn read_key() -> Option<console::Key> {
+    console::Term::stdout().read_key().ok()
+}
+
 pub fn get_input(prompt: &str) -> String {
     get_input_opt(prompt).unwrap_or_default()
 }